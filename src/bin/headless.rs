@@ -0,0 +1,74 @@
+//! Runs the reactor simulation without a window and prints the outcome.
+//!
+//! Usage: `headless [--ticks <count>] [--seed <seed>] [--replay <file>] [--record <file>] [--balance <file>] [--difficulty <name>] [--layout <file>] [--controller <name>]`
+
+use std::process::ExitCode;
+
+use reactor::{
+    gameplay::{Balance, CONTROLLERS, ReactorLayout, ReplayFile},
+    headless::{HeadlessConfig, run_headless},
+};
+
+fn main() -> ExitCode {
+    let (config, record_path) = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!(
+                "usage: headless [--ticks <count>] [--seed <seed>] [--replay <file>] [--record <file>] [--balance <file>] [--difficulty <name>] [--layout <file>] [--controller <name>]"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = run_headless(config);
+    println!("seed: {}", report.seed);
+    println!("difficulty: {}", report.difficulty.label());
+    println!("ticks: {}", report.ticks);
+    println!("power generated: {}", report.power_generated);
+    println!("power demand: {}", report.power_demand);
+    match report.cause {
+        Some(cause) => println!("game over: {cause:?}"),
+        None => println!("game over: none"),
+    }
+
+    if let Some(path) = record_path {
+        if let Err(err) = report.recording.save(&path) {
+            eprintln!("failed to save replay to {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> anyhow::Result<(HeadlessConfig, Option<String>)> {
+    let mut config = HeadlessConfig::default();
+    let mut record_path = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::format_err!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--ticks" => config.ticks = value()?.parse()?,
+            "--seed" => config.seed = Some(value()?.parse()?),
+            "--replay" => config.replay = Some(ReplayFile::load(value()?)?),
+            "--record" => record_path = Some(value()?),
+            "--balance" => config.balance = Balance::load(value()?)?,
+            "--difficulty" => config.difficulty = value()?.parse()?,
+            "--layout" => config.layout = ReactorLayout::load(value()?)?,
+            "--controller" => {
+                let name = value()?;
+                let (_, controller) = CONTROLLERS
+                    .into_iter()
+                    .find(|(controller, _)| *controller == name)
+                    .ok_or_else(|| anyhow::format_err!("unknown controller {name:?}"))?;
+                config.controller = Some(controller());
+            }
+            _ => anyhow::bail!("unknown argument {arg}"),
+        }
+    }
+    Ok((config, record_path))
+}
@@ -0,0 +1,401 @@
+//! An optional controller that moves control rods to keep the power up with the demand.
+//!
+//! [`Autopilot`] is a PID [`ReactorController`]. Every power generation phase it compares how far
+//! the energy and the steam on hand fall short of the demand, and sets the insertion of its rods
+//! through [`PlayerAction`]s like the player would. It saturates when its rods are all the way in
+//! or out, and it breaks down now and then, more often while saturated, and has to be repaired.
+
+use bevy::prelude::*;
+use rand::{Rng, RngCore};
+
+use super::{legend::button_label, *};
+
+/// Chance per power generation phase for a running autopilot to break down.
+const FAILURE_CHANCE: f32 = 0.002;
+/// How much more likely a breakdown is while the autopilot is saturated.
+const SATURATED_FAILURE_FACTOR: f32 = 5.;
+/// Power generation phases it takes to repair a broken autopilot.
+const REPAIR_TICKS: usize = 60;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Autopilot>();
+    app.init_resource::<Autopilot>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_autopilot);
+    app.add_plugins(controller_plugin::<Autopilot>);
+}
+
+/// The autopilot panel, which isn't part of headless runs.
+pub(super) fn ui_plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_autopilot_panel);
+    app.add_systems(
+        Update,
+        update_autopilot_panel
+            .run_if(in_state(Screen::Gameplay).and(resource_changed::<Autopilot>)),
+    );
+}
+
+/// Gains of the [`Autopilot`], applied to the shortfall in seconds of demand growth.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct PidGains {
+    pub proportional: f32,
+    pub integral: f32,
+    pub derivative: f32,
+}
+
+impl Default for PidGains {
+    fn default() -> Self {
+        Self {
+            proportional: 0.1,
+            integral: 0.02,
+            derivative: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum AutopilotStatus {
+    #[default]
+    Off,
+    Tracking,
+    /// The rods are as far in or out as they go and can't make up the difference.
+    Saturated,
+    /// Broken down, ready to be switched on again after `ticks_left` power generation phases.
+    Failed {
+        ticks_left: usize,
+    },
+}
+
+impl AutopilotStatus {
+    fn label(&self) -> String {
+        match self {
+            Self::Off => "off".to_string(),
+            Self::Tracking => "tracking".to_string(),
+            Self::Saturated => "saturated".to_string(),
+            Self::Failed { ticks_left } => format!("repairing {ticks_left}"),
+        }
+    }
+}
+
+/// A PID controller that sets the insertion of [`Autopilot::rods`].
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Autopilot {
+    pub gains: PidGains,
+    /// Indices of the control rods the autopilot moves. An empty list hands it every rod.
+    pub rods: Vec<usize>,
+    pub status: AutopilotStatus,
+    /// Insertion the rods had when the autopilot took over, which the output is added to.
+    bias: Option<f32>,
+    integral: f32,
+    last_error: Option<f32>,
+}
+
+impl Autopilot {
+    /// An autopilot that takes over every rod right away, e.g. for headless runs.
+    pub fn switched_on() -> Self {
+        Self {
+            status: AutopilotStatus::Tracking,
+            ..default()
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.status,
+            AutopilotStatus::Tracking | AutopilotStatus::Saturated
+        )
+    }
+
+    /// Takes over the rods from their current insertion. A broken autopilot stays off.
+    pub fn switch_on(&mut self) {
+        if self.status != AutopilotStatus::Off {
+            return;
+        }
+        self.bias = None;
+        self.integral = 0.;
+        self.last_error = None;
+        self.status = AutopilotStatus::Tracking;
+    }
+
+    pub fn switch_off(&mut self) {
+        if self.is_running() {
+            self.status = AutopilotStatus::Off;
+        }
+    }
+
+    fn controls(&self, rod: usize) -> bool {
+        self.rods.is_empty() || self.rods.contains(&rod)
+    }
+}
+
+impl ReactorController for Autopilot {
+    fn is_active(&self) -> bool {
+        self.status != AutopilotStatus::Off
+    }
+
+    /// Runs the controller once per power generation phase.
+    fn act(&mut self, observation: &Observation, rng: &mut dyn RngCore) -> Vec<PlayerAction> {
+        if observation.phase != Phase::PowerGeneration {
+            return Vec::new();
+        }
+        if let AutopilotStatus::Failed { ticks_left } = &mut self.status {
+            *ticks_left = ticks_left.saturating_sub(1);
+            if *ticks_left == 0 {
+                self.status = AutopilotStatus::Off;
+            }
+            return Vec::new();
+        }
+        // The rods can't be moved during a SCRAM, winding up the integral would only overshoot.
+        if !self.is_running() || observation.scram_latched {
+            return Vec::new();
+        }
+
+        let mut failure_chance = FAILURE_CHANCE;
+        if self.status == AutopilotStatus::Saturated {
+            failure_chance *= SATURATED_FAILURE_FACTOR;
+        }
+        if rng.random_range(0.0..1.0) < failure_chance {
+            self.status = AutopilotStatus::Failed {
+                ticks_left: REPAIR_TICKS,
+            };
+            return Vec::new();
+        }
+
+        let rods: Vec<_> = observation
+            .control_rods
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.controls(*index))
+            .map(|(index, rod)| (index, rod.target.unwrap_or(rod.insertion)))
+            .collect();
+        let bias = *self.bias.get_or_insert_with(|| {
+            rods.iter().map(|(_, insertion)| insertion).sum::<f32>() / rods.len().max(1) as f32
+        });
+
+        // Positive when more power is needed than the steam on hand can cover.
+        let shortfall =
+            observation.demand as f32 - observation.energy as f32 - observation.steam as f32;
+        let error = shortfall / observation.demand_growth.max(1) as f32;
+        let derivative = self.last_error.map_or(0., |last| error - last);
+        self.last_error = Some(error);
+
+        // Pulling the rods out raises the power, so the output lowers the insertion.
+        let output = self.gains.proportional * error
+            + self.gains.integral * (self.integral + error)
+            + self.gains.derivative * derivative;
+        let insertion = bias - output;
+        if (0.0..=1.0).contains(&insertion) {
+            // The integral only winds up while the rods can still follow it.
+            self.integral += error;
+            self.status = AutopilotStatus::Tracking;
+        } else {
+            self.status = AutopilotStatus::Saturated;
+        }
+
+        let percent = (insertion.clamp(0., 1.) * 100.).round() as u8;
+        rods.into_iter()
+            .filter(|(_, current)| (current * 100.).round() as u8 != percent)
+            .map(|(rod, _)| PlayerAction::SetControlRodTarget { rod, percent })
+            .collect()
+    }
+}
+
+/// Switches the autopilot off for a new run, keeping its gains.
+fn reset_autopilot(mut autopilot: ResMut<Autopilot>) {
+    *autopilot = Autopilot {
+        gains: autopilot.gains,
+        ..default()
+    };
+}
+
+#[derive(Component, Clone, Copy)]
+enum AutopilotButton {
+    Power,
+    /// Multiplies a gain by the given factor.
+    Gain(Gain, f32),
+    AssignSelection,
+}
+
+#[derive(Clone, Copy)]
+enum Gain {
+    Proportional,
+    Integral,
+    Derivative,
+}
+
+impl Gain {
+    const ALL: [Self; 3] = [Self::Proportional, Self::Integral, Self::Derivative];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Proportional => "P",
+            Self::Integral => "I",
+            Self::Derivative => "D",
+        }
+    }
+
+    fn get(&self, gains: &PidGains) -> f32 {
+        match self {
+            Self::Proportional => gains.proportional,
+            Self::Integral => gains.integral,
+            Self::Derivative => gains.derivative,
+        }
+    }
+
+    fn get_mut<'a>(&self, gains: &'a mut PidGains) -> &'a mut f32 {
+        match self {
+            Self::Proportional => &mut gains.proportional,
+            Self::Integral => &mut gains.integral,
+            Self::Derivative => &mut gains.derivative,
+        }
+    }
+}
+
+/// Text in the autopilot panel that shows part of the [`Autopilot`].
+#[derive(Component, Clone, Copy)]
+enum AutopilotText {
+    Power,
+    Status,
+    Gain(Gain),
+    Rods,
+}
+
+fn spawn_autopilot_panel(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let small_button_mesh = meshes.add(Rectangle::from_length(26.));
+    let button_material = materials.add(Color::WHITE);
+
+    let root = legend::spawn_side_panel(
+        &mut commands,
+        "Autopilot panel",
+        legend::SidePanel {
+            name: "autopilot",
+            order: 2,
+        },
+        -30.,
+    );
+
+    commands
+        .spawn((
+            Name::new("Autopilot power"),
+            AutopilotButton::Power,
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(60., 26.))),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(-70., -50., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![(button_label(""), AutopilotText::Power)],
+        ))
+        .observe(on_click_autopilot);
+    commands.spawn((
+        panel_text(),
+        AutopilotText::Status,
+        ChildOf(root),
+        Transform::from_xyz(40., -50., 1.),
+    ));
+
+    for (i, gain) in Gain::ALL.into_iter().enumerate() {
+        let y = -100. - 40. * i as f32;
+        commands.spawn((
+            panel_text(),
+            AutopilotText::Gain(gain),
+            ChildOf(root),
+            Transform::from_xyz(-40., y, 1.),
+        ));
+        for (label, x, factor) in [("-", 50., 1. / 1.5), ("+", 85., 1.5)] {
+            commands
+                .spawn((
+                    Name::new(format!("{} {label}", gain.label())),
+                    AutopilotButton::Gain(gain, factor),
+                    ChildOf(root),
+                    Mesh2d(small_button_mesh.clone()),
+                    MeshMaterial2d(button_material.clone()),
+                    Transform::from_xyz(x, y, 1.),
+                    Pickable::default(),
+                    PlaysClickSound,
+                    PlaysHoverSound,
+                    children![button_label(label)],
+                ))
+                .observe(on_click_autopilot);
+        }
+    }
+
+    commands
+        .spawn((
+            Name::new("Assign selection"),
+            AutopilotButton::AssignSelection,
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(130., 26.))),
+            MeshMaterial2d(button_material),
+            Transform::from_xyz(-35., -230., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("assign rods")],
+        ))
+        .observe(on_click_autopilot);
+    commands.spawn((
+        panel_text(),
+        AutopilotText::Rods,
+        ChildOf(root),
+        Transform::from_xyz(80., -230., 1.),
+    ));
+}
+
+fn panel_text() -> impl Bundle {
+    (
+        Name::new("Autopilot text"),
+        Text2d::default(),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        TextColor::WHITE,
+    )
+}
+
+fn on_click_autopilot(
+    trigger: Trigger<Pointer<Click>>,
+    buttons: Query<&AutopilotButton>,
+    mut autopilot: ResMut<Autopilot>,
+    selected: Query<&ControlRodIndex, With<banks::SelectedRod>>,
+) {
+    let Ok(button) = buttons.get(trigger.target()) else {
+        return;
+    };
+    match *button {
+        AutopilotButton::Power if autopilot.is_running() => autopilot.switch_off(),
+        AutopilotButton::Power => autopilot.switch_on(),
+        AutopilotButton::Gain(gain, factor) => *gain.get_mut(&mut autopilot.gains) *= factor,
+        // Without a selection the autopilot gets every rod back.
+        AutopilotButton::AssignSelection => {
+            let mut rods: Vec<_> = selected.iter().map(|index| index.0).collect();
+            rods.sort();
+            autopilot.rods = rods;
+        }
+    }
+}
+
+fn update_autopilot_panel(
+    autopilot: Res<Autopilot>,
+    mut texts: Query<(&AutopilotText, &mut Text2d)>,
+) {
+    for (text, mut value) in &mut texts {
+        value.0 = match text {
+            AutopilotText::Power if autopilot.is_running() => "off".to_string(),
+            AutopilotText::Power => "on".to_string(),
+            AutopilotText::Status => autopilot.status.label(),
+            AutopilotText::Gain(gain) => {
+                format!("{} {:.3}", gain.label(), gain.get(&autopilot.gains))
+            }
+            AutopilotText::Rods if autopilot.rods.is_empty() => "all rods".to_string(),
+            AutopilotText::Rods => format!("{} rods", autopilot.rods.len()),
+        };
+    }
+}
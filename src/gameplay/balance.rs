@@ -0,0 +1,496 @@
+//! Tuning values for the simulation, loaded from `assets/reactor.balance.ron`.
+//!
+//! The file is validated when it's loaded and, with the `dev_native` feature, reloaded whenever it
+//! changes on disk. A file that fails validation is rejected and the previous values stay in use.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::asset_tracking::LoadResource;
+
+/// Registers the [`Balance`] resource with its built-in defaults.
+pub fn plugin(app: &mut App) {
+    app.register_type::<Balance>();
+    app.init_resource::<Balance>();
+}
+
+/// Loads [`Balance`] from the asset file and keeps it up to date.
+pub(super) fn asset_plugin(app: &mut App) {
+    app.init_asset::<Balance>();
+    app.init_asset_loader::<BalanceLoader>();
+    app.load_resource::<BalanceAssets>();
+    app.add_systems(PreUpdate, apply_loaded_balance);
+}
+
+#[derive(Asset, Resource, Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct Balance {
+    /// Distance a thermal neutron travels per second.
+    pub neutron_speed: f32,
+    pub fast_neutron_speed: f32,
+    pub neutron_lifetime_sec: f32,
+    pub max_neutrons_released_per_tick: usize,
+    /// Chance for each neutron of a fission to be held back as a precursor instead of released
+    /// right away.
+    pub delayed_neutron_fraction: f32,
+    /// Chance for each precursor to release its neutron during a neutron release phase.
+    pub precursor_decay_chance: f32,
+    /// Chance for each fuel rod to start as uranium rather than xenon.
+    pub initial_uranium_chance: f32,
+    pub iodine_per_fission: f32,
+    /// Fraction of the iodine in a cell that decays into xenon per tick.
+    pub iodine_decay_per_tick: f32,
+    /// Fraction of the xenon in a cell that decays per tick.
+    pub xenon_decay_per_tick: f32,
+    /// Chance for each unit of xenon in a cell to swallow a neutron hitting the fuel.
+    pub xenon_capture_chance: f32,
+    /// Amount of xenon that stops a fuel rod from working.
+    pub xenon_poison_level: f32,
+    /// Fraction of its reactivity a fuel rod has lost right before it's spent.
+    pub burnup_reactivity_loss: f32,
+    /// Ticks it takes to replace a spent fuel rod, during which the cell produces nothing.
+    pub refuel_ticks: usize,
+    /// Fraction of the full range a control rod moves per second.
+    pub control_rod_insertion_speed: f32,
+    /// Fraction of the full range a control rod moves per second during a SCRAM.
+    pub scram_insertion_speed: f32,
+    /// Time before a SCRAM can be reset.
+    pub scram_cooldown_sec: f32,
+    /// Amount added to the power demand by every SCRAM.
+    pub scram_demand_penalty: usize,
+    pub auto_scram: AutoScram,
+    pub water_created_per_tick: usize,
+    pub initial_water_stored: usize,
+    pub max_water_stored: usize,
+    pub initial_water_flow: usize,
+    pub max_water_flow: usize,
+    /// Most water particles a coolant pump can move into its cell per tick.
+    pub max_pump_rate: usize,
+    /// Power a running pump draws per water particle of its rate.
+    pub pump_power_per_water: f32,
+    /// Pressure of its cell above which a pump trips.
+    pub pump_trip_pressure: usize,
+    /// Ticks a tripped pump waits before it restarts.
+    pub pump_restart_ticks: usize,
+    pub steam_generated_per_water: usize,
+    pub steam_vented_per_tick: usize,
+    /// Fraction of the stored steam a fully open throttle valve lets into the turbine per tick.
+    pub turbine_valve_flow: f32,
+    /// Fraction of its speed the turbine keeps per tick, the rest follows the steam let in.
+    pub turbine_inertia: f32,
+    /// Efficiency of the generator at the speed that matches the load.
+    pub turbine_peak_efficiency: f32,
+    /// Efficiency lost per squared difference between the load ratio, the speed over the load,
+    /// and 1.
+    pub turbine_efficiency_falloff: f32,
+    /// Steam per tick the turbine is built to spin with.
+    pub turbine_rated_speed: f32,
+    /// Multiple of [`Balance::turbine_rated_speed`] above which the turbine trips.
+    pub turbine_overspeed: f32,
+    /// Steam per tick below which a turbine that was running trips.
+    pub turbine_min_steam: f32,
+    /// Ticks a tripped turbine waits before it restarts.
+    pub turbine_restart_ticks: usize,
+    pub pressure_warn_level: usize,
+    pub pressure_explosion_level: usize,
+    /// Temperature every cell starts at and cools down towards.
+    pub ambient_temperature: f32,
+    pub heat_per_fission: f32,
+    /// Heat carried away by every water particle that boils.
+    pub heat_per_boil: f32,
+    /// Fraction of the temperature difference to each neighbouring cell that flows over per tick.
+    pub heat_conduction_per_tick: f32,
+    /// Fraction of the heat above ambient a cell loses per tick.
+    pub heat_loss_per_tick: f32,
+    /// Temperature at which a cell boils its water without the help of neutrons.
+    pub boiling_temperature: f32,
+    pub max_water_boiled_per_tick: usize,
+    pub overheat_warn_temperature: f32,
+    pub overheat_temperature: f32,
+    /// Time before the power demand starts growing.
+    pub tutorial_sec: f32,
+    pub increase_power_demand_sec: f32,
+    /// How often the amount added to the power demand doubles.
+    pub increase_power_demand_increase_rate_sec: f32,
+    pub power_warn_ticks: usize,
+    pub power_failure_ticks: usize,
+    pub fuels: FuelTypes,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self {
+            neutron_speed: 65.,
+            fast_neutron_speed: 110.,
+            neutron_lifetime_sec: 45.,
+            max_neutrons_released_per_tick: 3,
+            delayed_neutron_fraction: 0.3,
+            precursor_decay_chance: 0.25,
+            initial_uranium_chance: 0.65,
+            iodine_per_fission: 1.0,
+            iodine_decay_per_tick: 0.05,
+            xenon_decay_per_tick: 0.03,
+            xenon_capture_chance: 0.05,
+            xenon_poison_level: 12.0,
+            burnup_reactivity_loss: 0.5,
+            refuel_ticks: 15,
+            control_rod_insertion_speed: 0.1,
+            scram_insertion_speed: 1.0,
+            scram_cooldown_sec: 20.,
+            scram_demand_penalty: 30,
+            auto_scram: AutoScram::default(),
+            water_created_per_tick: 200,
+            initial_water_stored: 10,
+            max_water_stored: 2000,
+            initial_water_flow: 3,
+            max_water_flow: 50,
+            max_pump_rate: 5,
+            pump_power_per_water: 0.5,
+            pump_trip_pressure: 20,
+            pump_restart_ticks: 10,
+            steam_generated_per_water: 2,
+            steam_vented_per_tick: 3,
+            turbine_valve_flow: 0.5,
+            turbine_inertia: 0.7,
+            turbine_peak_efficiency: 1.0,
+            turbine_efficiency_falloff: 0.5,
+            turbine_rated_speed: 20.0,
+            turbine_overspeed: 2.0,
+            turbine_min_steam: 0.5,
+            turbine_restart_ticks: 8,
+            pressure_warn_level: 15,
+            pressure_explosion_level: 30,
+            ambient_temperature: 20.,
+            heat_per_fission: 12.,
+            heat_per_boil: 8.,
+            heat_conduction_per_tick: 0.05,
+            heat_loss_per_tick: 0.02,
+            boiling_temperature: 100.,
+            max_water_boiled_per_tick: 2,
+            overheat_warn_temperature: 250.,
+            overheat_temperature: 350.,
+            tutorial_sec: 15.,
+            increase_power_demand_sec: 1.,
+            increase_power_demand_increase_rate_sec: 12.,
+            power_warn_ticks: 5,
+            power_failure_ticks: 20,
+            fuels: FuelTypes::default(),
+        }
+    }
+}
+
+impl Balance {
+    /// Reads and validates a balance file outside of the asset system, e.g. for headless runs.
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let balance: Self = ron::from_str(&text)?;
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let chances = [
+            ("delayed_neutron_fraction", self.delayed_neutron_fraction),
+            ("precursor_decay_chance", self.precursor_decay_chance),
+            ("initial_uranium_chance", self.initial_uranium_chance),
+            ("iodine_decay_per_tick", self.iodine_decay_per_tick),
+            ("xenon_decay_per_tick", self.xenon_decay_per_tick),
+            ("xenon_capture_chance", self.xenon_capture_chance),
+            ("heat_loss_per_tick", self.heat_loss_per_tick),
+            ("burnup_reactivity_loss", self.burnup_reactivity_loss),
+            ("turbine_valve_flow", self.turbine_valve_flow),
+            ("turbine_peak_efficiency", self.turbine_peak_efficiency),
+        ];
+        for (name, chance) in chances {
+            anyhow::ensure!(
+                (0.0..=1.0).contains(&chance),
+                "{name} must be between 0 and 1, got {chance}"
+            );
+        }
+
+        let positive = [
+            ("neutron_speed", self.neutron_speed),
+            ("fast_neutron_speed", self.fast_neutron_speed),
+            ("neutron_lifetime_sec", self.neutron_lifetime_sec),
+            ("heat_per_fission", self.heat_per_fission),
+            ("iodine_per_fission", self.iodine_per_fission),
+            ("xenon_poison_level", self.xenon_poison_level),
+            ("heat_per_boil", self.heat_per_boil),
+            (
+                "control_rod_insertion_speed",
+                self.control_rod_insertion_speed,
+            ),
+            ("scram_insertion_speed", self.scram_insertion_speed),
+            ("turbine_rated_speed", self.turbine_rated_speed),
+            ("increase_power_demand_sec", self.increase_power_demand_sec),
+            (
+                "increase_power_demand_increase_rate_sec",
+                self.increase_power_demand_increase_rate_sec,
+            ),
+        ];
+        for (name, value) in positive {
+            anyhow::ensure!(value > 0.0, "{name} must be positive, got {value}");
+        }
+        anyhow::ensure!(
+            self.scram_cooldown_sec >= 0.0,
+            "scram_cooldown_sec can't be negative, got {}",
+            self.scram_cooldown_sec
+        );
+        anyhow::ensure!(
+            self.tutorial_sec >= 0.0,
+            "tutorial_sec can't be negative, got {}",
+            self.tutorial_sec
+        );
+
+        anyhow::ensure!(
+            self.initial_water_stored <= self.max_water_stored,
+            "initial_water_stored ({}) exceeds max_water_stored ({})",
+            self.initial_water_stored,
+            self.max_water_stored
+        );
+        anyhow::ensure!(
+            (1..=self.max_water_flow).contains(&self.initial_water_flow),
+            "initial_water_flow must be between 1 and max_water_flow ({}), got {}",
+            self.max_water_flow,
+            self.initial_water_flow
+        );
+        anyhow::ensure!(
+            (0.0..1.0).contains(&self.turbine_inertia),
+            "turbine_inertia must be at least 0 and below 1, got {}",
+            self.turbine_inertia
+        );
+        anyhow::ensure!(
+            self.turbine_efficiency_falloff >= 0.0,
+            "turbine_efficiency_falloff can't be negative, got {}",
+            self.turbine_efficiency_falloff
+        );
+        anyhow::ensure!(
+            self.turbine_overspeed > 1.0,
+            "turbine_overspeed must be above 1, got {}",
+            self.turbine_overspeed
+        );
+        anyhow::ensure!(
+            self.turbine_min_steam >= 0.0,
+            "turbine_min_steam can't be negative, got {}",
+            self.turbine_min_steam
+        );
+        anyhow::ensure!(
+            self.turbine_restart_ticks > 0,
+            "turbine_restart_ticks must be positive"
+        );
+        anyhow::ensure!(
+            self.pump_power_per_water >= 0.0,
+            "pump_power_per_water can't be negative, got {}",
+            self.pump_power_per_water
+        );
+        anyhow::ensure!(
+            self.pump_trip_pressure < self.pressure_explosion_level,
+            "pump_trip_pressure ({}) must be below pressure_explosion_level ({})",
+            self.pump_trip_pressure,
+            self.pressure_explosion_level
+        );
+        anyhow::ensure!(
+            self.pump_restart_ticks > 0,
+            "pump_restart_ticks must be positive"
+        );
+        anyhow::ensure!(
+            self.pressure_warn_level < self.pressure_explosion_level,
+            "pressure_warn_level ({}) must be below pressure_explosion_level ({})",
+            self.pressure_warn_level,
+            self.pressure_explosion_level
+        );
+        // A cell has up to six neighbours, more would let heat flow back and forth and blow up.
+        anyhow::ensure!(
+            (0.0..=1. / 6.).contains(&self.heat_conduction_per_tick),
+            "heat_conduction_per_tick must be between 0 and 1/6, got {}",
+            self.heat_conduction_per_tick
+        );
+        anyhow::ensure!(
+            self.ambient_temperature < self.boiling_temperature
+                && self.boiling_temperature < self.overheat_warn_temperature
+                && self.overheat_warn_temperature < self.overheat_temperature,
+            "temperatures must increase from ambient_temperature ({}) over boiling_temperature ({}) \
+             and overheat_warn_temperature ({}) to overheat_temperature ({})",
+            self.ambient_temperature,
+            self.boiling_temperature,
+            self.overheat_warn_temperature,
+            self.overheat_temperature
+        );
+        if let Some(pressure) = self.auto_scram.pressure {
+            anyhow::ensure!(
+                pressure < self.pressure_explosion_level,
+                "auto_scram.pressure ({pressure}) must be below pressure_explosion_level ({})",
+                self.pressure_explosion_level
+            );
+        }
+        if let Some(temperature) = self.auto_scram.temperature {
+            anyhow::ensure!(
+                temperature < self.overheat_temperature,
+                "auto_scram.temperature ({temperature}) must be below overheat_temperature ({})",
+                self.overheat_temperature
+            );
+        }
+        anyhow::ensure!(
+            self.power_warn_ticks < self.power_failure_ticks,
+            "power_warn_ticks ({}) must be below power_failure_ticks ({})",
+            self.power_warn_ticks,
+            self.power_failure_ticks
+        );
+        self.fuels.validate()
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct BalanceAssets {
+    #[dependency]
+    balance: Handle<Balance>,
+}
+
+impl FromWorld for BalanceAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            balance: assets.load("reactor.balance.ron"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    type Asset = Balance;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Balance, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let balance: Balance = ron::de::from_bytes(&bytes)?;
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
+
+/// Copies the loaded file into [`Balance`] and the running reactor, on load and on hot reload.
+///
+/// The running reactor gets the file as its [`Difficulty`] scales it.
+fn apply_loaded_balance(
+    mut events: EventReader<AssetEvent<Balance>>,
+    balance_assets: Option<Res<BalanceAssets>>,
+    assets: Res<Assets<Balance>>,
+    mut balance: ResMut<Balance>,
+    reactor: Option<ResMut<Reactor>>,
+) {
+    let Some(balance_assets) = balance_assets else {
+        return;
+    };
+    let modified = events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Modified { id } if *id == balance_assets.balance.id()
+        )
+    });
+    if !balance_assets.is_added() && !modified {
+        return;
+    }
+    let Some(loaded) = assets.get(&balance_assets.balance) else {
+        return;
+    };
+
+    info!("applying balance from reactor.balance.ron");
+    balance.set_if_neq(loaded.clone());
+    if let Some(mut reactor) = reactor {
+        match reactor.difficulty.apply(loaded) {
+            Ok(balance) => reactor.balance = balance,
+            Err(err) => warn!(
+                "keeping the balance of the running reactor, {} rejects the file: {err}",
+                reactor.difficulty.label()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_and_shipped_balance_are_valid() {
+        Balance::default().validate().unwrap();
+        let shipped: Balance =
+            ron::from_str(include_str!("../../assets/reactor.balance.ron")).unwrap();
+        shipped.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        let broken = [
+            Balance {
+                delayed_neutron_fraction: 1.5,
+                ..default()
+            },
+            Balance {
+                neutron_speed: 0.,
+                ..default()
+            },
+            Balance {
+                initial_water_stored: 3000,
+                max_water_stored: 2000,
+                ..default()
+            },
+            Balance {
+                turbine_overspeed: 1.,
+                ..default()
+            },
+        ];
+        for balance in broken {
+            assert!(balance.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_levels_out_of_order() {
+        let broken = [
+            Balance {
+                pressure_warn_level: 30,
+                pressure_explosion_level: 30,
+                ..default()
+            },
+            Balance {
+                power_warn_ticks: 20,
+                power_failure_ticks: 20,
+                ..default()
+            },
+            Balance {
+                boiling_temperature: 400.,
+                ..default()
+            },
+            Balance {
+                auto_scram: AutoScram {
+                    pressure: Some(30),
+                    temperature: None,
+                },
+                pressure_explosion_level: 30,
+                ..default()
+            },
+        ];
+        for balance in broken {
+            assert!(balance.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Selecting control rods and moving them together as banks.
+//!
+//! Rods are selected by shift-clicking them or by dragging a box around them with shift held. The
+//! arrow keys move the selected rods. A selection can be kept as a bank of the layout, which is
+//! saved with it, and the bank panel, one of the [`legend::SidePanel`]s, selects and moves the
+//! banks.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use super::{legend::button_label, *};
+use crate::menus::Menu;
+
+/// Change of the setpoint per key press or click on a bank's buttons.
+const BANK_STEP: f32 = 0.1;
+/// Boxes smaller than this are shift-clicks rather than box selections.
+const MIN_BOX_SIZE: f32 = 8.;
+const SELECTION_BOX_COLOR: Color = Color::srgba(1., 1., 1., 0.15);
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<SelectedRod>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_bank_panel.after(spawn_reactor),
+    );
+    app.add_systems(
+        Update,
+        (move_selected_rods_with_keys, box_select)
+            .run_if(in_state(Screen::Gameplay).and(in_state(Menu::None))),
+    );
+    app.add_observer(toggle_rod_selection)
+        .add_observer(show_selection_outline)
+        .add_observer(hide_selection_outline)
+        .add_observer(rebuild_bank_rows);
+}
+
+/// A control rod that moves with the other selected rods.
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct SelectedRod;
+
+/// Selects the targeted control rod, or deselects it if it's selected.
+#[derive(Event)]
+pub struct ToggleRodSelection;
+
+/// Spawns a row in the bank panel for each bank of the run's layout.
+#[derive(Event)]
+struct RebuildBankRows;
+
+#[derive(Component)]
+struct SelectionOutline;
+
+/// A box being dragged out to select the control rods in it.
+#[derive(Component)]
+pub(super) struct SelectionBox {
+    start: Vec2,
+}
+
+#[derive(Component)]
+struct BankRows;
+
+fn toggle_rod_selection(
+    trigger: Trigger<ToggleRodSelection>,
+    mut commands: Commands,
+    rods: Query<Has<SelectedRod>, With<ControlRod>>,
+) {
+    match rods.get(trigger.target()) {
+        Ok(true) => {
+            commands
+                .entity(trigger.target())
+                .try_remove::<SelectedRod>();
+        }
+        Ok(false) => {
+            commands.entity(trigger.target()).try_insert(SelectedRod);
+        }
+        Err(_) => {}
+    }
+}
+
+fn show_selection_outline(
+    trigger: Trigger<OnAdd, SelectedRod>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        Name::new("Selection outline"),
+        SelectionOutline,
+        ChildOf(trigger.target()),
+        Mesh2d(meshes.add(Rectangle::from_length(CONTROL_ROD_RADIUS * 2. + 10.))),
+        MeshMaterial2d(materials.add(Color::WHITE)),
+        Transform::from_xyz(0., 0., -1.),
+        Pickable::IGNORE,
+    ));
+}
+
+fn hide_selection_outline(
+    trigger: Trigger<OnRemove, SelectedRod>,
+    mut commands: Commands,
+    rods: Query<&Children>,
+    outlines: Query<(), With<SelectionOutline>>,
+) {
+    if let Ok(children) = rods.get(trigger.target()) {
+        for &entity in children.into_iter() {
+            if outlines.contains(entity) {
+                commands.entity(entity).try_despawn();
+            }
+        }
+    }
+}
+
+fn box_select(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut boxes: Query<(Entity, &SelectionBox, &mut Sprite, &mut Transform)>,
+    rods: Query<(Entity, &GlobalTransform), With<ControlRod>>,
+) {
+    let (camera, camera_transform) = *camera;
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    if buttons.just_pressed(MouseButton::Left) && control_rods::shift_pressed(&keys) {
+        commands.spawn((
+            Name::new("Selection box"),
+            SelectionBox { start: cursor },
+            Sprite::from_color(SELECTION_BOX_COLOR, Vec2::ZERO),
+            Transform::from_translation(cursor.extend(60.)),
+            Pickable::IGNORE,
+            StateScoped(Screen::Gameplay),
+        ));
+        return;
+    }
+
+    let Ok((entity, selection, mut sprite, mut transform)) = boxes.single_mut() else {
+        return;
+    };
+    let rect = Rect::from_corners(selection.start, cursor);
+    if buttons.pressed(MouseButton::Left) {
+        sprite.custom_size = Some(rect.size());
+        transform.translation = rect.center().extend(60.);
+        return;
+    }
+
+    commands.entity(entity).despawn();
+    if rect.width().max(rect.height()) < MIN_BOX_SIZE {
+        return;
+    }
+    for (rod, transform) in &rods {
+        if rect.contains(transform.translation().xy()) {
+            commands.entity(rod).try_insert(SelectedRod);
+        }
+    }
+}
+
+fn move_selected_rods_with_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    rods: Query<(&ControlRodIndex, &ControlRodInsertion, &ControlRodTarget), With<SelectedRod>>,
+) {
+    let target = |current: f32| {
+        if keys.just_pressed(KeyCode::ArrowUp) {
+            Some(current + BANK_STEP)
+        } else if keys.just_pressed(KeyCode::ArrowDown) {
+            Some(current - BANK_STEP)
+        } else if keys.just_pressed(KeyCode::PageUp) {
+            Some(1.)
+        } else if keys.just_pressed(KeyCode::PageDown) {
+            Some(0.)
+        } else {
+            None
+        }
+    };
+    for (index, insertion, current) in &rods {
+        if let Some(target) = target(current.0.unwrap_or(insertion.0)) {
+            commands.trigger(control_rods::set_target(index.0, target));
+        }
+    }
+}
+
+fn spawn_bank_panel(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let button_material = materials.add(Color::WHITE);
+
+    let root = legend::spawn_side_panel(
+        &mut commands,
+        "Bank panel",
+        legend::SidePanel {
+            name: "banks",
+            order: 1,
+        },
+        -50.,
+    );
+
+    commands
+        .spawn((
+            Name::new("New bank"),
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(110., 26.))),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(-40., -50., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("new bank")],
+        ))
+        .observe(on_click_new_bank);
+    commands
+        .spawn((
+            Name::new("Clear selection"),
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(80., 26.))),
+            MeshMaterial2d(button_material),
+            Transform::from_xyz(70., -50., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("clear")],
+        ))
+        .observe(on_click_clear_selection);
+
+    commands.spawn((
+        Name::new("Bank rows"),
+        BankRows,
+        ChildOf(root),
+        Transform::from_xyz(0., -100., 0.),
+        Visibility::Inherited,
+    ));
+    commands.trigger(RebuildBankRows);
+}
+
+fn rebuild_bank_rows(
+    _: Trigger<RebuildBankRows>,
+    mut commands: Commands,
+    reactor: Res<Reactor>,
+    rows: Single<Entity, With<BankRows>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.entity(*rows).despawn_related::<Children>();
+
+    let name_mesh = meshes.add(Rectangle::new(100., 26.));
+    let button_mesh = meshes.add(Rectangle::new(40., 26.));
+    let remove_mesh = meshes.add(Rectangle::new(30., 26.));
+    let button_material = materials.add(Color::WHITE);
+
+    for (i, bank) in reactor.layout.banks.iter().enumerate() {
+        let y = -40. * i as f32;
+        let buttons = [
+            (
+                bank.name.clone(),
+                -55.,
+                name_mesh.clone(),
+                BankButton::Select,
+            ),
+            (
+                "in".to_string(),
+                25.,
+                button_mesh.clone(),
+                BankButton::Move(BANK_STEP),
+            ),
+            (
+                "out".to_string(),
+                70.,
+                button_mesh.clone(),
+                BankButton::Move(-BANK_STEP),
+            ),
+            (
+                "x".to_string(),
+                110.,
+                remove_mesh.clone(),
+                BankButton::Remove,
+            ),
+        ];
+        for (label, x, mesh, button) in buttons {
+            let name = bank.name.clone();
+            commands
+                .spawn((
+                    Name::new(format!("{} {label}", bank.name)),
+                    ChildOf(*rows),
+                    Mesh2d(mesh),
+                    MeshMaterial2d(button_material.clone()),
+                    Transform::from_xyz(x, y, 1.),
+                    Pickable::default(),
+                    PlaysClickSound,
+                    PlaysHoverSound,
+                    children![button_label(label)],
+                ))
+                .observe(
+                    move |_: Trigger<Pointer<Click>>,
+                          commands: Commands,
+                          reactor: ResMut<Reactor>,
+                          next_layout: ResMut<ReactorLayout>,
+                          rods: Query<BankRod>| {
+                        on_click_bank(&name, button, commands, reactor, next_layout, rods);
+                    },
+                );
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BankButton {
+    Select,
+    Move(f32),
+    Remove,
+}
+
+type BankRod = (
+    Entity,
+    &'static ControlRod,
+    &'static ControlRodIndex,
+    &'static ControlRodInsertion,
+    &'static ControlRodTarget,
+);
+
+/// Keeps the banks of the run for the next runs on the same layout and saves them with it to
+/// [`LAYOUT_SAVE_PATH`], so they're still there after a restart.
+fn keep_banks(next: &mut ReactorLayout, run: &ReactorLayout) {
+    if !next.same_slots(run) {
+        return;
+    }
+    next.banks = run.banks.clone();
+    #[cfg(not(target_family = "wasm"))]
+    if let Err(err) = next.save(LAYOUT_SAVE_PATH) {
+        warn!("failed to save banks to {LAYOUT_SAVE_PATH}: {err}");
+    }
+}
+
+fn on_click_bank(
+    name: &str,
+    button: BankButton,
+    mut commands: Commands,
+    mut reactor: ResMut<Reactor>,
+    mut next_layout: ResMut<ReactorLayout>,
+    rods: Query<BankRod>,
+) {
+    let Some(bank) = reactor.layout.banks.iter().find(|bank| bank.name == name) else {
+        return;
+    };
+    let in_bank = |rod: &ControlRod| bank.rods.contains(&rod.0);
+    match button {
+        BankButton::Select => {
+            for (entity, rod, ..) in &rods {
+                if in_bank(rod) {
+                    commands.entity(entity).try_insert(SelectedRod);
+                } else {
+                    commands.entity(entity).try_remove::<SelectedRod>();
+                }
+            }
+        }
+        BankButton::Move(step) => {
+            for (_, rod, index, insertion, target) in &rods {
+                if in_bank(rod) {
+                    let current = target.0.unwrap_or(insertion.0);
+                    commands.trigger(control_rods::set_target(index.0, current + step));
+                }
+            }
+        }
+        BankButton::Remove => {
+            reactor.layout.banks.retain(|bank| bank.name != name);
+            keep_banks(&mut next_layout, &reactor.layout);
+            commands.trigger(RebuildBankRows);
+        }
+    }
+}
+
+fn on_click_new_bank(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut reactor: ResMut<Reactor>,
+    mut next_layout: ResMut<ReactorLayout>,
+    selected: Query<&ControlRod, With<SelectedRod>>,
+) {
+    let mut rods: Vec<_> = selected.iter().map(|rod| rod.0).collect();
+    if rods.is_empty() {
+        return;
+    }
+    rods.sort_by_key(|pos| (pos.y, pos.x));
+    reactor.layout.add_bank(rods);
+    keep_banks(&mut next_layout, &reactor.layout);
+    commands.trigger(RebuildBankRows);
+}
+
+fn on_click_clear_selection(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    selected: Query<Entity, With<SelectedRod>>,
+) {
+    for entity in &selected {
+        commands.entity(entity).try_remove::<SelectedRod>();
+    }
+}
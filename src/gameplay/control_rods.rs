@@ -2,13 +2,21 @@ use bevy::prelude::*;
 
 use super::*;
 
+/// Distance a rod has to be dragged to move its setpoint over the whole range.
+const DRAG_PIXELS_PER_RANGE: f32 = 150.;
+/// Change of the setpoint per step of the scroll wheel.
+const SCROLL_STEP: f32 = 0.05;
+/// Drags shorter than this still count as clicks.
+const MIN_DRAG_DISTANCE: f32 = 4.;
+
 pub fn plugin(app: &mut App) {
+    app.register_type::<ControlRodDrag>();
+
     app.add_observer(setup_control_rod_on_add)
         .add_observer(hide_movement_indicators);
     app.add_systems(
         Update,
         (
-            update_insertions,
             update_insertion_indicators,
             update_movement_indicators,
             update_materials,
@@ -17,47 +25,114 @@ pub fn plugin(app: &mut App) {
     );
 }
 
+/// A setpoint being dragged on a control rod, which is applied when the drag ends.
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct ControlRodDrag {
+    target: f32,
+    distance: f32,
+}
+
 fn setup_control_rod_on_add(trigger: Trigger<OnAdd, ControlRod>, mut commands: Commands) {
-    commands.entity(trigger.target()).observe(on_click);
+    commands
+        .entity(trigger.target())
+        .observe(on_click)
+        .observe(on_drag)
+        .observe(on_drag_end)
+        .observe(on_scroll);
 }
 
 fn on_click(
     trigger: Trigger<Pointer<Click>>,
     mut commands: Commands,
-    mut query: Query<(Option<&mut ControlRodMovement>, &ControlRodInsertion)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    query: Query<(&ControlRodIndex, Option<&ControlRodDrag>)>,
 ) {
-    if let Ok((maybe_movement, insertion)) = query.get_mut(trigger.target()) {
-        if let Some(mut movement) = maybe_movement {
-            movement.reverse();
-        } else if insertion.0 > 0.5 {
-            commands
-                .entity(trigger.target())
-                .try_insert(ControlRodMovement::Down);
-        } else {
-            commands
-                .entity(trigger.target())
-                .try_insert(ControlRodMovement::Up);
+    if shift_pressed(&keys) {
+        commands.trigger_targets(banks::ToggleRodSelection, trigger.target());
+        return;
+    }
+    if let Ok((index, drag)) = query.get(trigger.target()) {
+        // Letting go of a rod after dragging it clicks it as well.
+        if drag.is_some_and(|drag| drag.distance >= MIN_DRAG_DISTANCE) {
+            return;
         }
+        commands.trigger(PlayerAction::ToggleControlRod { rod: index.0 });
     }
 }
 
-fn update_insertions(
+fn on_drag(
+    trigger: Trigger<Pointer<Drag>>,
     mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut ControlRodInsertion, &ControlRodMovement)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    query: Query<(
+        &ControlRodInsertion,
+        &ControlRodTarget,
+        Option<&ControlRodDrag>,
+    )>,
 ) {
-    for (entity, mut insertion, movement) in &mut query {
-        insertion.0 += time.delta_secs()
-            * match movement {
-                ControlRodMovement::Up => CONTROL_ROD_INSERTION_SPEED,
-                ControlRodMovement::Down => -CONTROL_ROD_INSERTION_SPEED,
-            };
-
-        if insertion.0 >= 1.0 || insertion.0 <= 0.0 {
-            insertion.0 = insertion.0.clamp(0.0, 1.0);
-            commands.entity(entity).try_remove::<ControlRodMovement>();
-        }
+    // Dragging with shift held selects the rods in a box instead.
+    if shift_pressed(&keys) {
+        return;
     }
+    let Ok((insertion, target, drag)) = query.get(trigger.target()) else {
+        return;
+    };
+    let drag = drag.copied().unwrap_or(ControlRodDrag {
+        target: target.0.unwrap_or(insertion.0),
+        distance: 0.,
+    });
+    // The pointer moves down the screen with growing y, dragging up inserts the rod.
+    let delta = trigger.event().delta;
+    commands
+        .entity(trigger.target())
+        .try_insert(ControlRodDrag {
+            target: (drag.target - delta.y / DRAG_PIXELS_PER_RANGE).clamp(0., 1.),
+            distance: drag.distance + delta.length(),
+        });
+}
+
+fn on_drag_end(
+    trigger: Trigger<Pointer<DragEnd>>,
+    mut commands: Commands,
+    query: Query<(&ControlRodIndex, &ControlRodDrag)>,
+) {
+    let Ok((index, drag)) = query.get(trigger.target()) else {
+        return;
+    };
+    if drag.distance >= MIN_DRAG_DISTANCE {
+        commands.trigger(set_target(index.0, drag.target));
+    }
+    commands
+        .entity(trigger.target())
+        .try_remove::<ControlRodDrag>();
+}
+
+fn on_scroll(
+    trigger: Trigger<Pointer<Scroll>>,
+    mut commands: Commands,
+    query: Query<(&ControlRodIndex, &ControlRodInsertion, &ControlRodTarget)>,
+) {
+    let Ok((index, insertion, target)) = query.get(trigger.target()) else {
+        return;
+    };
+    let scroll = trigger.event().y;
+    if scroll == 0. {
+        return;
+    }
+    let current = target.0.unwrap_or(insertion.0);
+    commands.trigger(set_target(index.0, current + SCROLL_STEP * scroll.signum()));
+}
+
+pub(super) fn set_target(rod: usize, target: f32) -> PlayerAction {
+    PlayerAction::SetControlRodTarget {
+        rod,
+        percent: (target.clamp(0., 1.) * 100.).round() as u8,
+    }
+}
+
+pub(super) fn shift_pressed(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
 }
 
 fn update_movement_indicators(
@@ -96,17 +171,36 @@ fn hide_movement_indicators(
     }
 }
 
+/// Shows the insertion of each rod, and the setpoint it's moving to or being dragged to.
 fn update_insertion_indicators(
     control_rods: Query<
-        (&ControlRodInsertion, &Children),
-        (With<ControlRod>, Changed<ControlRodInsertion>),
+        (
+            &ControlRodInsertion,
+            &ControlRodTarget,
+            Option<&ControlRodDrag>,
+            &Children,
+        ),
+        (
+            With<ControlRod>,
+            Or<(
+                Changed<ControlRodInsertion>,
+                Changed<ControlRodTarget>,
+                Changed<ControlRodDrag>,
+            )>,
+        ),
     >,
     mut indicators: Query<&mut Text2d, With<ControlRodInsertionIndicator>>,
 ) {
-    for (insertion, children) in &control_rods {
+    for (insertion, target, drag, children) in &control_rods {
+        let target = drag.map(|drag| drag.target).or(target.0);
         for &entity in children.into_iter() {
             if let Ok(mut text) = indicators.get_mut(entity) {
-                text.0 = format!("{:.0}%", insertion.0 * 100.);
+                text.0 = match target {
+                    Some(target) => {
+                        format!("{:.0}%\nto {:.0}%", insertion.0 * 100., target * 100.)
+                    }
+                    None => format!("{:.0}%", insertion.0 * 100.),
+                };
             }
         }
     }
@@ -0,0 +1,230 @@
+//! Programs that operate the reactor.
+//!
+//! A [`ReactorController`] sees an [`Observation`] of the reactor after every simulation tick and
+//! answers with [`PlayerAction`]s, which are recorded and applied just like the player's. Any
+//! resource that implements the trait can be run with [`controller_plugin`]. The game runs the
+//! [`Autopilot`] that way, and the [`Operator`] resource holds a controller plugged in from
+//! outside, e.g. by the headless runner.
+
+use bevy::prelude::*;
+use rand::RngCore;
+
+use super::*;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Operator>();
+    app.add_plugins(controller_plugin::<Operator>);
+}
+
+/// Runs the controller in resource `C` after every simulation tick.
+pub fn controller_plugin<C: ReactorController + Resource>(app: &mut App) {
+    app.add_systems(
+        RunSimulation,
+        run_controller::<C>
+            .after(step_reactor)
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<C>))
+            .in_set(PausableSystems),
+    );
+}
+
+/// A program that operates the reactor.
+pub trait ReactorController: Send + Sync + 'static {
+    /// Whether the controller wants to see the ticks at all.
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    /// Decides what to do after a tick. Randomness should come from `rng` so runs can be
+    /// reproduced from their seed.
+    fn act(&mut self, observation: &Observation, rng: &mut dyn RngCore) -> Vec<PlayerAction>;
+}
+
+/// The built-in controllers by name, e.g. for picking one on the command line.
+pub const CONTROLLERS: [(&str, fn() -> Box<dyn ReactorController>); 2] = [
+    ("autopilot", || Box::new(Autopilot::switched_on())),
+    ("script", || {
+        Box::new(OperatorScript::running(DEFAULT_SCRIPT))
+    }),
+];
+
+/// The controller plugged into the current run from outside the game, if any.
+#[derive(Resource, Default)]
+pub struct Operator(pub Option<Box<dyn ReactorController>>);
+
+impl ReactorController for Operator {
+    fn is_active(&self) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|controller| controller.is_active())
+    }
+
+    fn act(&mut self, observation: &Observation, rng: &mut dyn RngCore) -> Vec<PlayerAction> {
+        match &mut self.0 {
+            Some(controller) => controller.act(observation, rng),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// What a [`ReactorController`] gets to see of the reactor.
+#[derive(Clone, Debug)]
+pub struct Observation {
+    /// Number of simulation ticks that have run.
+    pub tick: u64,
+    /// The phase that ran in the last tick.
+    pub phase: Phase,
+    pub cells: Vec<CellObservation>,
+    pub control_rods: Vec<ControlRodObservation>,
+    /// Water particles in the water container.
+    pub water: usize,
+    pub water_flow: usize,
+    /// Steam particles in the steam container.
+    pub steam: usize,
+    pub energy: usize,
+    pub demand: usize,
+    /// Amount the demand currently grows by every [`Balance::increase_power_demand_sec`].
+    pub demand_growth: usize,
+    pub scram_latched: bool,
+    pub turbine: TurbineObservation,
+}
+
+#[derive(Clone, Debug)]
+pub struct CellObservation {
+    pub position: Position,
+    pub fuel: FuelRod,
+    pub water: usize,
+    pub steam: usize,
+    pub pressure: usize,
+    pub temperature: f32,
+    pub pump_rate: usize,
+    pub pump_tripped: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ControlRodObservation {
+    pub position: Position,
+    pub insertion: f32,
+    /// The insertion the rod is moving to, if it's moving.
+    pub target: Option<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TurbineObservation {
+    /// Opening of the throttle valve from 0 to 1.
+    pub throttle: f32,
+    /// Steam per tick the turbine spins with, which slowly follows the steam let in.
+    pub speed: f32,
+    /// Power the grid and the pumps asked for during the last tick.
+    pub load: usize,
+    pub efficiency: f32,
+    pub tripped: bool,
+}
+
+impl Observation {
+    pub fn new(reactor: &ReactorState, tick: u64) -> Self {
+        Self {
+            tick,
+            phase: reactor.phase.previous(),
+            cells: reactor
+                .cells
+                .iter()
+                .map(|cell| CellObservation {
+                    position: cell.position,
+                    fuel: cell.fuel,
+                    water: cell.water,
+                    steam: cell.steam,
+                    pressure: cell.pressure(),
+                    temperature: cell.temperature,
+                    pump_rate: cell.pump.rate,
+                    pump_tripped: cell.pump.tripped > 0,
+                })
+                .collect(),
+            control_rods: reactor
+                .control_rods
+                .iter()
+                .map(|rod| ControlRodObservation {
+                    position: rod.position,
+                    insertion: rod.insertion,
+                    target: rod.target,
+                })
+                .collect(),
+            water: reactor.water,
+            water_flow: reactor.water_flow,
+            steam: reactor.steam,
+            energy: reactor.energy,
+            demand: reactor.demand.demand,
+            demand_growth: reactor.demand.delta,
+            scram_latched: reactor.scram.latched,
+            turbine: TurbineObservation {
+                throttle: reactor.turbine.throttle,
+                speed: reactor.turbine.speed,
+                load: reactor.turbine.load,
+                efficiency: reactor.turbine.efficiency,
+                tripped: reactor.turbine.tripped > 0,
+            },
+        }
+    }
+}
+
+fn run_controller<C: ReactorController + Resource>(
+    mut commands: Commands,
+    mut controller: ResMut<C>,
+    reactor: Res<Reactor>,
+    time: Res<Time<Simulation>>,
+    mut rng: ResMut<SimulationRng>,
+) {
+    if !controller.is_active() {
+        return;
+    }
+    let observation = Observation::new(&reactor, time.context().ticks());
+    for action in controller.act(&observation, rng.stream(RngStream::Controller)) {
+        commands.trigger(action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reactor(rng: &mut SimulationRng) -> ReactorState {
+        ReactorState::new(
+            &ReactorLayout::default(),
+            &Balance::default(),
+            Difficulty::Normal,
+            rng,
+        )
+    }
+
+    #[test]
+    fn observation_mirrors_the_reactor() {
+        let mut rng = SimulationRng::new(1);
+        let mut reactor = reactor(&mut rng);
+        let phase = reactor.phase;
+        reactor.step(&mut rng);
+        reactor.cells[0].water = 4;
+        reactor.cells[0].steam = 2;
+        reactor.cells[0].pump.rate = 3;
+        reactor.control_rods[1].target = Some(0.5);
+        reactor.demand.demand = 12;
+
+        let observation = Observation::new(&reactor, 1);
+        assert_eq!(observation.tick, 1);
+        assert_eq!(observation.phase, phase);
+        assert_eq!(observation.cells.len(), reactor.cells.len());
+        let cell = &observation.cells[0];
+        assert_eq!(cell.position, reactor.cells[0].position);
+        assert_eq!((cell.water, cell.steam, cell.pressure), (4, 2, 6));
+        assert_eq!(cell.pump_rate, 3);
+        assert_eq!(observation.control_rods[1].target, Some(0.5));
+        assert_eq!(observation.demand, 12);
+        assert_eq!(observation.steam, reactor.steam);
+    }
+
+    #[test]
+    fn operator_without_a_controller_does_nothing() {
+        let mut operator = Operator::default();
+        assert!(!operator.is_active());
+        let observation = Observation::new(&reactor(&mut SimulationRng::new(1)), 0);
+        assert!(operator.act(&observation, &mut rand::rng()).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! Difficulty presets picked before a run.
+//!
+//! Easy and hard scale the values in [`Balance`] that decide how forgiving a run is. Normal and
+//! custom play with the balance file as it is.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Difficulty>();
+    app.init_resource::<Difficulty>();
+}
+
+/// The difficulty of the next run.
+#[derive(
+    Resource, Clone, Copy, Default, Debug, Eq, PartialEq, Hash, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl Difficulty {
+    pub const ALL: [Self; 4] = [Self::Easy, Self::Normal, Self::Hard, Self::Custom];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Normal => "normal",
+            Self::Hard => "hard",
+            Self::Custom => "custom",
+        }
+    }
+
+    /// The balance a run on this difficulty plays with, or an error if scaling `balance` broke
+    /// one of its rules, e.g. by lowering the explosion pressure below an auto-SCRAM level.
+    pub fn apply(&self, balance: &Balance) -> anyhow::Result<Balance> {
+        let scale = match self {
+            Self::Easy => Scale {
+                tutorial_sec: 2.0,
+                increase_power_demand_increase_rate_sec: 1.6,
+                pressure_levels: 4. / 3.,
+                iodine_per_fission: 0.5,
+                power_ticks: 2.0,
+            },
+            Self::Hard => Scale {
+                tutorial_sec: 1. / 3.,
+                increase_power_demand_increase_rate_sec: 2. / 3.,
+                pressure_levels: 0.8,
+                iodine_per_fission: 1.5,
+                power_ticks: 0.6,
+            },
+            Self::Normal | Self::Custom => return Ok(balance.clone()),
+        };
+
+        let scaled = |value: usize, factor: f32| ((value as f32 * factor).round() as usize).max(1);
+        let mut balance = balance.clone();
+        balance.tutorial_sec *= scale.tutorial_sec;
+        balance.increase_power_demand_increase_rate_sec *=
+            scale.increase_power_demand_increase_rate_sec;
+        balance.pressure_warn_level = scaled(balance.pressure_warn_level, scale.pressure_levels);
+        balance.pressure_explosion_level =
+            scaled(balance.pressure_explosion_level, scale.pressure_levels);
+        balance.iodine_per_fission *= scale.iodine_per_fission;
+        balance.power_warn_ticks = scaled(balance.power_warn_ticks, scale.power_ticks);
+        balance.power_failure_ticks = scaled(balance.power_failure_ticks, scale.power_ticks);
+        balance.validate()?;
+        Ok(balance)
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.label() == s)
+            .ok_or_else(|| anyhow::format_err!("unknown difficulty {s:?}"))
+    }
+}
+
+/// The factors a preset scales the balance by.
+struct Scale {
+    tutorial_sec: f32,
+    increase_power_demand_increase_rate_sec: f32,
+    /// Applies to both the warning and the explosion level.
+    pressure_levels: f32,
+    iodine_per_fission: f32,
+    /// Applies to both the warning and the failure countdown.
+    power_ticks: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_and_custom_keep_the_balance_file() {
+        let balance = Balance {
+            tutorial_sec: 42.,
+            pressure_explosion_level: 50,
+            ..default()
+        };
+        assert_eq!(Difficulty::Normal.apply(&balance).unwrap(), balance);
+        assert_eq!(Difficulty::Custom.apply(&balance).unwrap(), balance);
+    }
+
+    #[test]
+    fn easy_and_hard_scale_the_balance_file() {
+        let balance = Balance {
+            tutorial_sec: 30.,
+            pressure_warn_level: 20,
+            pressure_explosion_level: 40,
+            ..default()
+        };
+
+        let easy = Difficulty::Easy.apply(&balance).unwrap();
+        let hard = Difficulty::Hard.apply(&balance).unwrap();
+        assert!(easy.tutorial_sec > balance.tutorial_sec);
+        assert!(hard.tutorial_sec < balance.tutorial_sec);
+        assert!(easy.pressure_explosion_level > 40);
+        assert!(hard.pressure_explosion_level < 40);
+        assert!(easy.iodine_per_fission < balance.iodine_per_fission);
+        assert!(hard.power_failure_ticks < balance.power_failure_ticks);
+    }
+
+    #[test]
+    fn rejects_a_preset_that_breaks_the_balance() {
+        let mut balance = Balance::default();
+        balance.auto_scram.pressure = Some(balance.pressure_explosion_level - 1);
+        assert!(Difficulty::Normal.apply(&balance).is_ok());
+        assert!(Difficulty::Hard.apply(&balance).is_err());
+    }
+}
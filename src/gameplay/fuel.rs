@@ -1,4 +1,7 @@
+//! The kinds of fuel a rod can be made of and how they look.
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use super::*;
 
@@ -6,16 +9,214 @@ pub fn plugin(app: &mut App) {
     app.add_systems(Update, update_materials);
 }
 
+/// How a kind of fuel behaves in the reactor.
+#[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct FuelType {
+    /// sRGB color of fresh fuel rods.
+    pub color: [f32; 3],
+    /// Neutrons released by a single fission, prompt and delayed together.
+    pub neutron_yield: usize,
+    /// Chance for a fast neutron hitting the fuel to split it.
+    pub fast_fission_chance: f32,
+    pub thermal_fission_chance: f32,
+    /// Chance for a neutron that didn't split the fuel to be absorbed rather than fly through.
+    pub absorption_chance: f32,
+    /// Chance per attempt in a neutron release phase for the fuel to release a neutron on its own.
+    pub spontaneous_release_chance: f32,
+    /// Number of fissions after which the fuel is spent.
+    pub burnup_fissions: usize,
+    /// Fuel this one turns into once it has absorbed [`FuelType::breeding_captures`] neutrons.
+    pub breeds_into: Option<FuelRod>,
+    pub breeding_captures: usize,
+}
+
+/// The [`FuelType`] of every fuel that can be loaded into a rod.
+#[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct FuelTypes {
+    pub uranium: FuelType,
+    pub plutonium: FuelType,
+    pub thorium: FuelType,
+    pub mox: FuelType,
+}
+
+impl Default for FuelTypes {
+    fn default() -> Self {
+        Self {
+            uranium: FuelType {
+                color: [0.85, 0.85, 0.65],
+                neutron_yield: 3,
+                fast_fission_chance: 0.25,
+                thermal_fission_chance: 1.0,
+                absorption_chance: 0.0,
+                spontaneous_release_chance: 0.6,
+                burnup_fissions: 60,
+                breeds_into: None,
+                breeding_captures: 0,
+            },
+            plutonium: FuelType {
+                color: [0.85, 0.55, 0.60],
+                neutron_yield: 4,
+                fast_fission_chance: 0.4,
+                thermal_fission_chance: 0.9,
+                absorption_chance: 0.1,
+                spontaneous_release_chance: 0.4,
+                burnup_fissions: 50,
+                breeds_into: None,
+                breeding_captures: 0,
+            },
+            thorium: FuelType {
+                color: [0.60, 0.70, 0.90],
+                neutron_yield: 0,
+                fast_fission_chance: 0.0,
+                thermal_fission_chance: 0.0,
+                absorption_chance: 0.5,
+                spontaneous_release_chance: 0.0,
+                burnup_fissions: 1,
+                breeds_into: Some(FuelRod::Uranium),
+                breeding_captures: 5,
+            },
+            mox: FuelType {
+                color: [0.80, 0.65, 0.45],
+                neutron_yield: 3,
+                fast_fission_chance: 0.3,
+                thermal_fission_chance: 0.95,
+                absorption_chance: 0.05,
+                spontaneous_release_chance: 0.5,
+                burnup_fissions: 70,
+                breeds_into: None,
+                breeding_captures: 0,
+            },
+        }
+    }
+}
+
+impl FuelTypes {
+    /// The fuel type of `fuel`, or `None` for xenon and spent fuel.
+    pub fn get(&self, fuel: FuelRod) -> Option<&FuelType> {
+        match fuel {
+            FuelRod::Uranium => Some(&self.uranium),
+            FuelRod::Plutonium => Some(&self.plutonium),
+            FuelRod::Thorium => Some(&self.thorium),
+            FuelRod::Mox => Some(&self.mox),
+            FuelRod::Xenon | FuelRod::Spent => None,
+        }
+    }
+
+    pub fn color(&self, fuel: FuelRod) -> Color {
+        match fuel {
+            FuelRod::Xenon => XENON_COLOR,
+            FuelRod::Spent => SPENT_FUEL_COLOR,
+            _ => self
+                .get(fuel)
+                .map(|fuel| Color::srgb_from_array(fuel.color))
+                .unwrap_or(URANIUM_COLOR),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for fuel in FuelRod::ALL {
+            let Some(fuel_type) = self.get(fuel) else {
+                continue;
+            };
+            let name = fuel.label();
+            let chances = [
+                ("fast_fission_chance", fuel_type.fast_fission_chance),
+                ("thermal_fission_chance", fuel_type.thermal_fission_chance),
+                ("absorption_chance", fuel_type.absorption_chance),
+                (
+                    "spontaneous_release_chance",
+                    fuel_type.spontaneous_release_chance,
+                ),
+            ];
+            for (field, chance) in chances {
+                anyhow::ensure!(
+                    (0.0..=1.0).contains(&chance),
+                    "{field} of {name} must be between 0 and 1, got {chance}"
+                );
+            }
+            anyhow::ensure!(
+                fuel_type.burnup_fissions > 0,
+                "burnup_fissions of {name} must be positive"
+            );
+            if let Some(bred) = fuel_type.breeds_into {
+                anyhow::ensure!(
+                    bred != fuel && self.get(bred).is_some(),
+                    "{name} can't breed into {}",
+                    bred.label()
+                );
+                anyhow::ensure!(
+                    fuel_type.breeding_captures > 0,
+                    "breeding_captures of {name} must be positive"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fades fuel towards the spent fuel color as it burns up. Spent fuel that is being replaced
+/// shows the fresh rod faintly.
 fn update_materials(
     mut commands: Commands,
-    query: Query<(Entity, &FuelRod), Changed<FuelRod>>,
+    balance: Res<Balance>,
+    query: Query<
+        (Entity, &FuelRod, &Reactivity, &Refuelling),
+        Or<(Changed<FuelRod>, Changed<Reactivity>, Changed<Refuelling>)>,
+    >,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (entity, fuel_rod) in &query {
-        let material = materials.add(match fuel_rod {
-            FuelRod::Uranium => URANIUM_COLOR,
-            FuelRod::Xenon => XENON_COLOR,
-        });
-        commands.entity(entity).try_insert(MeshMaterial2d(material));
+    for (entity, &fuel_rod, reactivity, refuelling) in &query {
+        let material: ColorMaterial = match (fuel_rod, refuelling.0) {
+            (FuelRod::Xenon, _) => XENON_COLOR.into(),
+            (FuelRod::Spent, Some(fresh)) => ColorMaterial {
+                color: balance.fuels.color(fresh).with_alpha(0.4),
+                alpha_mode: AlphaMode2d::Blend,
+                ..default()
+            },
+            (FuelRod::Spent, None) => SPENT_FUEL_COLOR.into(),
+            (fuel, _) => SPENT_FUEL_COLOR
+                .mix(&balance.fuels.color(fuel), reactivity.0)
+                .into(),
+        };
+        commands
+            .entity(entity)
+            .try_insert(MeshMaterial2d(materials.add(material)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_valid(change: impl FnOnce(&mut FuelTypes)) -> bool {
+        let mut fuels = FuelTypes::default();
+        change(&mut fuels);
+        fuels.validate().is_ok()
+    }
+
+    #[test]
+    fn default_fuel_types_are_valid() {
+        FuelTypes::default().validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_broken_fuel_types() {
+        assert!(!is_valid(|fuels| fuels.plutonium.fast_fission_chance = 1.5));
+        assert!(!is_valid(|fuels| fuels.mox.absorption_chance = -0.1));
+        assert!(!is_valid(|fuels| fuels.uranium.burnup_fissions = 0));
+    }
+
+    #[test]
+    fn rejects_breeding_that_goes_nowhere() {
+        assert!(!is_valid(
+            |fuels| fuels.thorium.breeds_into = Some(FuelRod::Thorium)
+        ));
+        assert!(!is_valid(
+            |fuels| fuels.thorium.breeds_into = Some(FuelRod::Xenon)
+        ));
+        assert!(!is_valid(|fuels| fuels.thorium.breeding_captures = 0));
+        assert!(is_valid(
+            |fuels| fuels.thorium.breeds_into = Some(FuelRod::Plutonium)
+        ));
     }
 }
@@ -0,0 +1,590 @@
+//! The shape of the reactor core.
+//!
+//! A [`ReactorLayout`] is a grid of [`Slot`]s. Each slot holds a cell, a control rod or nothing,
+//! so cores of any size and shape can be described. The grid is centered on the origin and its
+//! first row is the top one.
+//!
+//! The grid is either a square lattice or a hexagonal one, see [`Lattice`]. On a hexagonal
+//! lattice [`Position`]s are axial coordinates: `x` is the column and `y` the row, with rows
+//! shifted by half a slot so that every slot touches six others.
+//!
+//! The layout editor saves to `saves/reactor.layout.ron`, which new runs start from. Until a
+//! layout has been saved they start from the one shipped in `assets/reactor.layout.ron`.
+
+use std::path::Path;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{CELL_OUTER_SIZE, CELL_RADIUS, FuelRod, Position};
+use crate::asset_tracking::LoadResource;
+
+/// Where the layout editor saves to, relative to the working directory.
+#[cfg(not(target_family = "wasm"))]
+pub const LAYOUT_SAVE_PATH: &str = "saves/reactor.layout.ron";
+
+/// Registers the [`ReactorLayout`] resource with the classic layout.
+pub fn plugin(app: &mut App) {
+    app.register_type::<ReactorLayout>();
+    app.init_resource::<ReactorLayout>();
+}
+
+/// Loads [`ReactorLayout`] from the asset file and keeps it up to date.
+pub(super) fn asset_plugin(app: &mut App) {
+    app.init_asset::<ReactorLayout>();
+    app.init_asset_loader::<LayoutLoader>();
+    app.load_resource::<LayoutAssets>();
+    app.add_systems(PreUpdate, apply_loaded_layout);
+}
+
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Reflect, Serialize, Deserialize)]
+pub enum Slot {
+    #[default]
+    Empty,
+    /// A cell starting with the given fuel, or with random fuel if it's `None`.
+    Cell(Option<FuelRod>),
+    ControlRod,
+}
+
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Lattice {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl Lattice {
+    /// The offsets to the slots sharing a side with a slot.
+    fn directions(&self) -> &'static [(i32, i32)] {
+        match self {
+            Self::Square => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Self::Hex => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)],
+        }
+    }
+
+    /// The positions sharing a side with `pos`.
+    pub fn adjacent(&self, pos: Position) -> impl Iterator<Item = Position> {
+        self.directions()
+            .iter()
+            .map(move |(x, y)| Position::new(pos.x + x, pos.y + y))
+    }
+
+    /// The positions of the cells around the cell at `pos` in the default layout.
+    pub fn neighbours(&self, pos: Position) -> Vec<Position> {
+        match self {
+            Self::Square => pos.neighbours().to_vec(),
+            Self::Hex => self.adjacent(pos).collect(),
+        }
+    }
+
+    /// The distance between the centers of two slots sharing a side.
+    pub fn pitch(&self) -> f32 {
+        match self {
+            Self::Square => CELL_OUTER_SIZE,
+            Self::Hex => HEX_PITCH,
+        }
+    }
+
+    /// The center of the slot at `pos`, relative to the center of the core.
+    pub fn translation(&self, pos: Position) -> Vec2 {
+        let (x, y) = (pos.x as f32, pos.y as f32);
+        let offset = match self {
+            Self::Square => Vec2::new(x, y),
+            Self::Hex => Vec2::new(x + y / 2., y * HEX_ROW_HEIGHT),
+        };
+        offset * self.pitch()
+    }
+
+    /// The position of the slot under `point`, relative to the center of the core.
+    pub fn position_at(&self, point: Vec2) -> Position {
+        let point = point / self.pitch();
+        match self {
+            Self::Square => {
+                let pos = point.round();
+                Position::new(pos.x as i32, pos.y as i32)
+            }
+            Self::Hex => {
+                let y = point.y / HEX_ROW_HEIGHT;
+                let x = point.x - y / 2.;
+                hex_round(x, y)
+            }
+        }
+    }
+}
+
+/// The distance between neighbouring slots of a hexagonal lattice. Unlike on a square lattice,
+/// neighbouring cells sit side by side, so they need room for both of them and a gap.
+const HEX_PITCH: f32 = 2. * CELL_RADIUS + 10.;
+
+/// The distance between two rows of a hexagonal lattice, relative to the distance between slots.
+const HEX_ROW_HEIGHT: f32 = 0.866_025_4;
+
+/// The hexagon containing fractional axial coordinates.
+fn hex_round(x: f32, y: f32) -> Position {
+    let z = -x - y;
+    let (mut rx, mut ry, rz) = (x.round(), y.round(), z.round());
+    let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+    if dx > dy && dx > dz {
+        rx = -ry - rz;
+    } else if dy > dz {
+        ry = -rx - rz;
+    }
+    Position::new(rx as i32, ry as i32)
+}
+
+/// The layout of the next run's core.
+#[derive(Asset, Resource, Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct ReactorLayout {
+    #[serde(default)]
+    pub lattice: Lattice,
+    pub rows: usize,
+    pub columns: usize,
+    /// The slots row by row, starting with the top row.
+    pub slots: Vec<Slot>,
+    #[serde(default)]
+    pub banks: Vec<RodBank>,
+}
+
+/// A named group of control rods that are moved together.
+#[derive(Clone, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct RodBank {
+    pub name: String,
+    pub rods: Vec<Position>,
+}
+
+impl Default for ReactorLayout {
+    /// The classic core: cells and control rods in a 7x7 checkerboard.
+    fn default() -> Self {
+        Self::checkerboard(7, 7)
+    }
+}
+
+impl ReactorLayout {
+    /// A core of alternating cells and control rods, with a control rod in the center.
+    pub fn checkerboard(rows: usize, columns: usize) -> Self {
+        let mut layout = Self {
+            lattice: Lattice::Square,
+            rows,
+            columns,
+            slots: vec![Slot::Empty; rows * columns],
+            banks: Vec::new(),
+        };
+        let positions: Vec<_> = layout.iter_all_positions().collect();
+        for pos in positions {
+            let slot = if pos.is_valid() {
+                Slot::Cell(None)
+            } else {
+                Slot::ControlRod
+            };
+            layout.set(pos, slot);
+        }
+        layout
+    }
+
+    /// A hexagonal core of the given radius, with every cell touching three control rods.
+    pub fn hexagon(radius: usize) -> Self {
+        let size = radius * 2 + 1;
+        let mut layout = Self {
+            lattice: Lattice::Hex,
+            rows: size,
+            columns: size,
+            slots: vec![Slot::Empty; size * size],
+            banks: Vec::new(),
+        };
+        let positions: Vec<_> = layout.iter_all_positions().collect();
+        for pos in positions {
+            let distance = pos.x.abs().max(pos.y.abs()).max((pos.x + pos.y).abs());
+            if distance as usize > radius {
+                continue;
+            }
+            let slot = if (pos.x - pos.y).rem_euclid(3) == 0 {
+                Slot::ControlRod
+            } else {
+                Slot::Cell(None)
+            };
+            layout.set(pos, slot);
+        }
+        layout
+    }
+
+    /// The default layout of a lattice.
+    pub fn classic(lattice: Lattice) -> Self {
+        match lattice {
+            Lattice::Square => Self::default(),
+            Lattice::Hex => Self::hexagon(3),
+        }
+    }
+
+    /// A copy of this layout with a different size. Slots outside the new grid are dropped.
+    pub fn resized(&self, rows: usize, columns: usize) -> Self {
+        let mut layout = Self {
+            lattice: self.lattice,
+            rows,
+            columns,
+            slots: vec![Slot::Empty; rows * columns],
+            banks: self.banks.clone(),
+        };
+        let positions: Vec<_> = layout.iter_all_positions().collect();
+        for pos in positions {
+            if let Some(slot) = self.get(pos) {
+                layout.set(pos, slot);
+            }
+        }
+        layout.prune_banks();
+        layout
+    }
+
+    /// Adds a bank of the given control rods under the next free name and returns the name.
+    pub fn add_bank(&mut self, rods: Vec<Position>) -> String {
+        let name = (b'A'..=b'Z')
+            .map(|letter| format!("bank {}", letter as char))
+            .find(|name| self.banks.iter().all(|bank| bank.name != *name))
+            .unwrap_or_else(|| format!("bank {}", self.banks.len() + 1));
+        self.banks.push(RodBank {
+            name: name.clone(),
+            rods,
+        });
+        self.prune_banks();
+        name
+    }
+
+    /// Whether both layouts place the same slots, whatever their banks.
+    pub fn same_slots(&self, other: &Self) -> bool {
+        self.lattice == other.lattice
+            && self.rows == other.rows
+            && self.columns == other.columns
+            && self.slots == other.slots
+    }
+
+    /// Removes positions that aren't control rods from the banks, and banks left empty.
+    fn prune_banks(&mut self) {
+        let mut banks = std::mem::take(&mut self.banks);
+        for bank in &mut banks {
+            bank.rods
+                .retain(|&pos| self.get(pos) == Some(Slot::ControlRod));
+        }
+        banks.retain(|bank| !bank.rods.is_empty());
+        self.banks = banks;
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let layout: Self = ron::from_str(&text)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// The layout saved from the editor, if there is one that can be loaded.
+    pub fn load_saved() -> Option<Self> {
+        #[cfg(not(target_family = "wasm"))]
+        if Path::new(LAYOUT_SAVE_PATH).exists() {
+            match Self::load(LAYOUT_SAVE_PATH) {
+                Ok(layout) => return Some(layout),
+                Err(err) => warn!("ignoring saved layout {LAYOUT_SAVE_PATH}: {err}"),
+            }
+        }
+        None
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.validate()?;
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+        let text = ron::ser::to_string_pretty(self, config)?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.rows > 0 && self.columns > 0,
+            "layout must have at least one row and column, got {}x{}",
+            self.columns,
+            self.rows
+        );
+        anyhow::ensure!(
+            self.slots.len() == self.rows * self.columns,
+            "layout of {}x{} needs {} slots, got {}",
+            self.columns,
+            self.rows,
+            self.rows * self.columns,
+            self.slots.len()
+        );
+        anyhow::ensure!(
+            self.slots.iter().any(|slot| matches!(slot, Slot::Cell(_))),
+            "layout must have at least one cell"
+        );
+        for bank in &self.banks {
+            for &pos in &bank.rods {
+                anyhow::ensure!(
+                    self.get(pos) == Some(Slot::ControlRod),
+                    "{} contains {}/{}, which isn't a control rod",
+                    bank.name,
+                    pos.x,
+                    pos.y
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, pos: Position) -> Option<usize> {
+        let column = pos.x + (self.columns / 2) as i32;
+        let row = (self.rows - 1 - self.rows / 2) as i32 - pos.y;
+        if !(0..self.columns as i32).contains(&column) || !(0..self.rows as i32).contains(&row) {
+            return None;
+        }
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    /// The slot at `pos`, or `None` if it's outside the grid.
+    pub fn get(&self, pos: Position) -> Option<Slot> {
+        self.index(pos).map(|index| self.slots[index])
+    }
+
+    pub fn set(&mut self, pos: Position, slot: Slot) {
+        if let Some(index) = self.index(pos) {
+            self.slots[index] = slot;
+            if slot != Slot::ControlRod && !self.banks.is_empty() {
+                self.prune_banks();
+            }
+        }
+    }
+
+    /// Every position of the grid, from the bottom row to the top one.
+    pub fn iter_all_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        let column_radius = (self.columns / 2) as i32;
+        let column_rem = (self.columns % 2) as i32;
+        let row_radius = (self.rows / 2) as i32;
+        let row_rem = (self.rows % 2) as i32;
+
+        (-row_radius..row_radius + row_rem).flat_map(move |y| {
+            (-column_radius..column_radius + column_rem).map(move |x| Position::new(x, y))
+        })
+    }
+
+    pub fn iter_slots(&self) -> impl Iterator<Item = (Position, Slot)> + '_ {
+        self.iter_all_positions()
+            .filter_map(|pos| self.get(pos).map(|slot| (pos, slot)))
+    }
+
+    /// The cells with the fuel they start with, if it's fixed.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Position, Option<FuelRod>)> + '_ {
+        self.iter_slots().filter_map(|(pos, slot)| match slot {
+            Slot::Cell(fuel) => Some((pos, fuel)),
+            _ => None,
+        })
+    }
+
+    pub fn iter_cell_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.iter_cells().map(|(pos, _)| pos)
+    }
+
+    pub fn iter_control_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.iter_slots()
+            .filter(|(_, slot)| *slot == Slot::ControlRod)
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn translation(&self, pos: Position) -> Vec2 {
+        self.lattice.translation(pos)
+    }
+
+    /// Whether `pos` is part of the core, i.e. holds a cell or a control rod.
+    pub fn contains(&self, pos: Position) -> bool {
+        self.get(pos).is_some_and(|slot| slot != Slot::Empty)
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LayoutAssets {
+    #[dependency]
+    pub layout: Handle<ReactorLayout>,
+}
+
+impl FromWorld for LayoutAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            layout: assets.load("reactor.layout.ron"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct LayoutLoader;
+
+impl AssetLoader for LayoutLoader {
+    type Asset = ReactorLayout;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<ReactorLayout, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let layout: ReactorLayout = ron::de::from_bytes(&bytes)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["layout.ron"]
+    }
+}
+
+/// Copies the saved layout, or else the shipped file, into [`ReactorLayout`], on load and on hot
+/// reload.
+fn apply_loaded_layout(
+    mut events: EventReader<AssetEvent<ReactorLayout>>,
+    layout_assets: Option<Res<LayoutAssets>>,
+    assets: Res<Assets<ReactorLayout>>,
+    mut layout: ResMut<ReactorLayout>,
+) {
+    let Some(layout_assets) = layout_assets else {
+        return;
+    };
+    let modified = events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Modified { id } if *id == layout_assets.layout.id()
+        )
+    });
+    if !layout_assets.is_added() && !modified {
+        return;
+    }
+    let Some(loaded) = assets.get(&layout_assets.layout) else {
+        return;
+    };
+
+    match ReactorLayout::load_saved() {
+        Some(saved) => {
+            info!("applying saved layout");
+            layout.set_if_neq(saved);
+        }
+        None => {
+            info!("applying layout from reactor.layout.ron");
+            layout.set_if_neq(loaded.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn validate_accepts_the_built_in_layouts() {
+        ReactorLayout::default().validate().unwrap();
+        let shipped: ReactorLayout =
+            ron::from_str(include_str!("../../assets/reactor.layout.ron")).unwrap();
+        shipped.validate().unwrap();
+    }
+
+    #[test]
+    fn banks_lose_the_rods_that_are_replaced() {
+        let mut layout = ReactorLayout::default();
+        let rods: Vec<_> = layout
+            .iter_all_positions()
+            .filter(|&pos| layout.get(pos) == Some(Slot::ControlRod))
+            .take(2)
+            .collect();
+        assert_eq!(layout.add_bank(rods.clone()), "bank A");
+
+        layout.set(rods[0], Slot::Cell(None));
+        assert_eq!(layout.banks[0].rods, [rods[1]]);
+        layout.set(rods[1], Slot::Empty);
+        assert!(layout.banks.is_empty());
+    }
+
+    #[test]
+    fn validate_rejects_banks_of_other_slots() {
+        let mut layout = ReactorLayout::default();
+        let cell = layout
+            .iter_all_positions()
+            .find(|&pos| matches!(layout.get(pos), Some(Slot::Cell(_))))
+            .unwrap();
+        layout.banks.push(RodBank {
+            name: "bank A".into(),
+            rods: vec![cell],
+        });
+        assert!(layout.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_broken_grids() {
+        let mut missing_slot = ReactorLayout::default();
+        missing_slot.slots.pop();
+        assert!(missing_slot.validate().is_err());
+
+        let no_cells = ReactorLayout {
+            rows: 3,
+            columns: 3,
+            slots: vec![Slot::ControlRod; 9],
+            ..default()
+        };
+        assert!(no_cells.validate().is_err());
+
+        let empty = ReactorLayout {
+            rows: 0,
+            columns: 0,
+            slots: Vec::new(),
+            ..default()
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn resizing_keeps_the_slots_that_still_fit() {
+        let layout = ReactorLayout::default();
+
+        let smaller = layout.resized(3, 3);
+        assert_eq!(smaller.slots.len(), 9);
+        for pos in smaller.iter_all_positions() {
+            assert_eq!(smaller.get(pos), layout.get(pos));
+        }
+
+        let larger = layout.resized(9, 9);
+        assert_eq!(larger.slots.len(), 81);
+        for pos in layout.iter_all_positions() {
+            assert_eq!(larger.get(pos), layout.get(pos));
+        }
+        assert_eq!(larger.get(Position::new(4, 4)), Some(Slot::Empty));
+    }
+
+    #[test]
+    fn hex_round_picks_the_nearest_hexagon() {
+        assert_eq!(hex_round(0.1, -0.1), Position::new(0, 0));
+        assert_eq!(hex_round(0.9, 0.05), Position::new(1, 0));
+        assert_eq!(hex_round(-0.3, -0.8), Position::new(0, -1));
+        // Rounding each coordinate alone would land on (1, 1), which is further away.
+        assert_ne!(hex_round(0.6, 0.6), Position::new(1, 1));
+    }
+
+    #[test]
+    fn hex_points_map_back_to_their_slot() {
+        let lattice = Lattice::Hex;
+        let layout = ReactorLayout::hexagon(3);
+        for pos in layout.iter_all_positions() {
+            let center = lattice.translation(pos);
+            for step in 0..12 {
+                let offset = Vec2::from_angle(step as f32 * PI / 6.) * lattice.pitch() * 0.45;
+                assert_eq!(lattice.position_at(center + offset), pos);
+            }
+        }
+    }
+}
@@ -1,62 +1,209 @@
-use bevy::prelude::*;
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
-use crate::screens::Screen;
+use crate::{menus::Menu, screens::Screen, theme::palette::BUTTON_TEXT};
 
 use super::*;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_layout);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (spawn_layout, spawn_side_panel_toggle),
+    );
+    app.add_systems(
+        Update,
+        cycle_side_panels
+            .run_if(input_just_pressed(KeyCode::KeyB))
+            .run_if(in_state(Screen::Gameplay).and(in_state(Menu::None))),
+    );
+    app.add_systems(
+        Update,
+        update_side_panel_toggle.run_if(in_state(Screen::Gameplay)),
+    );
 
     app.add_observer(add_legend_neutron)
         .add_observer(add_legend_water)
         .add_observer(add_legend_cell)
-        .add_observer(add_legend_uranium)
-        .add_observer(add_legend_xenon)
+        .add_observer(add_legend_fuel)
         .add_observer(add_legend_control_rod)
         .add_observer(add_legend_steam);
 }
 
-fn spawn_layout(mut commands: Commands) {
+/// One of the panels that take turns in the place of the legend, in the order they're shown.
+#[derive(Component, Clone, Copy)]
+pub struct SidePanel {
+    pub name: &'static str,
+    pub order: usize,
+}
+
+/// Spawns `panel` in the place of the legend, headed by its name `header_x` off its center. Only
+/// the first panel starts out shown.
+pub fn spawn_side_panel(
+    commands: &mut Commands,
+    name: &'static str,
+    panel: SidePanel,
+    header_x: f32,
+) -> Entity {
     let root = commands
         .spawn((
-            Name::new("Legend"),
+            Name::new(name),
+            panel,
             Transform::from_xyz(500., 250., 50.),
-            Visibility::Inherited,
+            if panel.order == 0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
             StateScoped(Screen::Gameplay),
         ))
         .id();
 
     commands.spawn((
-        Name::new("Legend text"),
-        Transform::from_xyz(-50.0, 0., 1.),
+        Name::new(format!("{name} text")),
+        Transform::from_xyz(header_x, 0., 1.),
         ChildOf(root),
-        Text2d::new("legend"),
+        Text2d::new(panel.name),
         TextFont {
             font_size: 36.,
             ..default()
         },
         TextColor::WHITE,
     ));
+    root
+}
+
+/// The text on a button of a side panel.
+pub fn button_label(text: impl Into<String>) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text2d::new(text),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+        Transform::from_xyz(0., 0., 1.),
+        Pickable::IGNORE,
+    )
+}
+
+#[derive(Component)]
+struct SidePanelToggleLabel;
+
+fn spawn_side_panel_toggle(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn((
+            Name::new("Side panel toggle"),
+            Mesh2d(meshes.add(Rectangle::new(90., 26.))),
+            MeshMaterial2d(materials.add(Color::WHITE)),
+            Transform::from_xyz(570., 300., 50.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            StateScoped(Screen::Gameplay),
+            children![(button_label(""), SidePanelToggleLabel)],
+        ))
+        .observe(on_click_side_panel_toggle);
+}
+
+/// Hides the side panel that's shown and shows the next one.
+fn cycle_side_panels(mut panels: Query<(&SidePanel, &mut Visibility)>) {
+    let mut panels: Vec<_> = panels.iter_mut().collect();
+    panels.sort_by_key(|(panel, _)| panel.order);
+    let shown = panels
+        .iter()
+        .position(|(_, visibility)| **visibility != Visibility::Hidden)
+        .unwrap_or(0);
+    let next = (shown + 1) % panels.len();
+    for (i, (_, visibility)) in panels.iter_mut().enumerate() {
+        **visibility = if i == next {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn on_click_side_panel_toggle(
+    _: Trigger<Pointer<Click>>,
+    panels: Query<(&SidePanel, &mut Visibility)>,
+) {
+    cycle_side_panels(panels);
+}
+
+/// Names the panel the toggle switches to next.
+fn update_side_panel_toggle(
+    panels: Query<(&SidePanel, &Visibility)>,
+    changed: Query<(), (With<SidePanel>, Or<(Added<SidePanel>, Changed<Visibility>)>)>,
+    mut label: Single<&mut Text2d, With<SidePanelToggleLabel>>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let mut panels: Vec<_> = panels.iter().collect();
+    panels.sort_by_key(|(panel, _)| panel.order);
+    let shown = panels
+        .iter()
+        .position(|(_, visibility)| **visibility != Visibility::Hidden)
+        .unwrap_or(0);
+    let (next, _) = panels[(shown + 1) % panels.len()];
+    label.0 = next.name.to_string();
+}
+
+fn spawn_layout(mut commands: Commands) {
+    let root = spawn_side_panel(
+        &mut commands,
+        "Legend",
+        SidePanel {
+            name: "legend",
+            order: 0,
+        },
+        -50.,
+    );
 
     let gap = 45.;
     let gap2 = 60.;
     let start = -60.;
-    let start3 = -110.;
 
-    legend(&mut commands, root, LegendNeutron, 0., start);
-    legend(&mut commands, root, LegendWater, 0., start - gap * 1.);
-    legend(&mut commands, root, LegendSteam, 0., start - gap * 2.);
+    legend(
+        &mut commands,
+        root,
+        LegendNeutron(NeutronEnergy::Fast),
+        0.,
+        start,
+    );
+    legend(
+        &mut commands,
+        root,
+        LegendNeutron(NeutronEnergy::Thermal),
+        0.,
+        start - gap,
+    );
+    legend(&mut commands, root, LegendWater, 0., start - gap * 2.);
+    legend(&mut commands, root, LegendSteam, 0., start - gap * 3.);
 
-    legend(&mut commands, root, LegendCell, 0., start - gap2 * 3.);
-    legend(&mut commands, root, LegendUranium, 0., start - gap2 * 4.);
-    legend(&mut commands, root, LegendXenon, 0., start - gap2 * 5.);
+    let cell = start - gap2 * 3.;
+    legend(&mut commands, root, LegendCell, 0., cell);
+    for (i, fuel) in FuelRod::ALL.into_iter().enumerate() {
+        legend(
+            &mut commands,
+            root,
+            LegendFuel(fuel),
+            0.,
+            cell - gap * (i + 1) as f32,
+        );
+    }
 
     legend(
         &mut commands,
         root,
         LegendControlRod,
         0.,
-        start3 - gap2 * 6.,
+        cell - gap * (FuelRod::ALL.len() as f32 + 1.5),
     );
 }
 
@@ -75,24 +222,29 @@ const FUEL_X: f32 = -75.;
 const FUEL_TEXT_X: f32 = -30.;
 
 #[derive(Component)]
-struct LegendNeutron;
+struct LegendNeutron(NeutronEnergy);
 
 fn add_legend_neutron(
     trigger: Trigger<OnAdd, LegendNeutron>,
     mut commands: Commands,
+    legends: Query<&LegendNeutron>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+) -> Result {
+    let energy = legends.get(trigger.target())?.0;
     commands.spawn((
         ChildOf(trigger.target()),
         Mesh2d(meshes.add(Circle::new(NEUTRON_RADIUS))),
-        MeshMaterial2d(materials.add(Color::from(NEUTRON_COLOR))),
+        MeshMaterial2d(materials.add(energy.color())),
         Transform::from_xyz(PARTICLE_X, 0., 0.),
     ));
     commands.spawn((
         ChildOf(trigger.target()),
         Transform::from_xyz(PARTICLE_TEXT_X, 0., 0.),
-        Text2d::new("neutron"),
+        Text2d::new(match energy {
+            NeutronEnergy::Fast => "fast neutron",
+            NeutronEnergy::Thermal => "slow neutron",
+        }),
         Anchor::CenterLeft,
         TextFont {
             font_size: 18.,
@@ -101,6 +253,7 @@ fn add_legend_neutron(
         UseBoldFont,
         TextColor::WHITE,
     ));
+    Ok(())
 }
 
 #[derive(Component)]
@@ -133,53 +286,27 @@ fn add_legend_cell(
 }
 
 #[derive(Component)]
-struct LegendUranium;
+struct LegendFuel(FuelRod);
 
-fn add_legend_uranium(
-    trigger: Trigger<OnAdd, LegendUranium>,
+fn add_legend_fuel(
+    trigger: Trigger<OnAdd, LegendFuel>,
     mut commands: Commands,
+    legends: Query<&LegendFuel>,
+    balance: Res<Balance>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+) -> Result {
+    let fuel = legends.get(trigger.target())?.0;
     commands.spawn((
         ChildOf(trigger.target()),
-        Mesh2d(meshes.add(Circle::new(FUEL_ROD_RADIUS))),
-        MeshMaterial2d(materials.add(URANIUM_COLOR)),
-        Transform::from_xyz(FUEL_X, 0., 0.),
-    ));
-    commands.spawn((
-        ChildOf(trigger.target()),
-        Transform::from_xyz(FUEL_TEXT_X, 0., 0.),
-        Text2d::new("uranium"),
-        Anchor::CenterLeft,
-        TextFont {
-            font_size: 18.,
-            ..default()
-        },
-        UseBoldFont,
-        TextColor::WHITE,
-    ));
-}
-
-#[derive(Component)]
-struct LegendXenon;
-
-fn add_legend_xenon(
-    trigger: Trigger<OnAdd, LegendXenon>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn((
-        ChildOf(trigger.target()),
-        Mesh2d(meshes.add(Circle::new(FUEL_ROD_RADIUS))),
-        MeshMaterial2d(materials.add(XENON_COLOR)),
+        Mesh2d(meshes.add(Circle::new(FUEL_ROD_RADIUS * 0.7))),
+        MeshMaterial2d(materials.add(balance.fuels.color(fuel))),
         Transform::from_xyz(FUEL_X, 0., 0.),
     ));
     commands.spawn((
         ChildOf(trigger.target()),
         Transform::from_xyz(FUEL_TEXT_X, 0., 0.),
-        Text2d::new("xenon"),
+        Text2d::new(fuel.label()),
         Anchor::CenterLeft,
         TextFont {
             font_size: 18.,
@@ -188,6 +315,7 @@ fn add_legend_xenon(
         UseBoldFont,
         TextColor::WHITE,
     ));
+    Ok(())
 }
 
 #[derive(Component)]
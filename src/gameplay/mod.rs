@@ -4,6 +4,7 @@ use avian2d::prelude::{Collider, CollisionLayers, RigidBody};
 use bevy::{
     prelude::*,
     sprite::{AlphaMode2d, Anchor},
+    time::Stopwatch,
 };
 
 use crate::{
@@ -14,46 +15,107 @@ use crate::{
 };
 
 pub mod audio;
+pub mod autopilot;
+pub mod balance;
+pub mod banks;
 pub mod constants;
 pub mod control_rods;
+pub mod controller;
 pub mod crt;
+pub mod difficulty;
 pub mod fuel;
+pub mod layout;
 pub mod legend;
+pub mod model;
 pub mod neutrons;
 pub mod particles;
 pub mod power;
+pub mod pumps;
+pub mod replay;
+pub mod rng;
+pub mod save;
 pub mod schedule;
+pub mod scram;
+pub mod script;
 pub mod simulation;
+pub mod speed;
 pub mod types;
 pub mod ui;
 
+pub use autopilot::*;
+pub use balance::*;
 pub use constants::*;
+pub use controller::*;
 pub use crt::*;
+pub use difficulty::*;
+pub use fuel::*;
+pub use layout::*;
+pub use model::*;
 pub use neutrons::*;
 pub use particles::*;
 pub use power::*;
+pub use pumps::*;
+pub use replay::*;
+pub use rng::*;
+pub use save::*;
 pub use schedule::*;
+pub use scram::*;
+pub use script::*;
 pub use simulation::*;
+pub use speed::*;
 pub use types::*;
 
 pub fn plugin(app: &mut App) {
+    app.add_plugins(core_plugin);
+    app.add_plugins(CrtPlugin);
+    app.add_plugins(legend::plugin);
+    app.add_plugins(banks::plugin);
+    app.add_plugins(autopilot::ui_plugin);
+    app.add_plugins(script::ui_plugin);
+    app.add_plugins(audio::plugin);
+    app.add_plugins(balance::asset_plugin);
+    app.add_plugins(layout::asset_plugin);
+    app.add_plugins(save::plugin);
+    app.add_plugins(speed::plugin);
+    app.add_plugins(scram::plugin);
+
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_camera);
+}
+
+/// Everything needed to run the reactor simulation, without rendering or audio.
+pub fn core_plugin(app: &mut App) {
     app.add_plugins(schedule::plugin);
+    app.add_plugins(balance::plugin);
+    app.add_plugins(difficulty::plugin);
+    app.add_plugins(layout::plugin);
+    app.add_plugins(rng::plugin);
     app.add_plugins(simulation::plugin);
     app.add_plugins(particles::plugin);
     app.add_plugins(types::plugin);
     app.add_plugins(neutrons::plugin);
     app.add_plugins(control_rods::plugin);
+    app.add_plugins(pumps::plugin);
     app.add_plugins(ui::plugin);
     app.add_plugins(fuel::plugin);
-    app.add_plugins(CrtPlugin);
     app.add_plugins(power::plugin);
-    app.add_plugins(legend::plugin);
-    app.add_plugins(audio::plugin);
+    app.add_plugins(replay::plugin);
+    app.add_plugins(controller::plugin);
+    app.add_plugins(script::plugin);
+    app.add_plugins(autopilot::plugin);
 
     app.init_resource::<GameplayAssets>();
     app.load_resource::<GameplayAssets>();
 
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_reactor);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (
+            reset_simulation_clock,
+            seed_simulation_rng,
+            create_reactor,
+            spawn_reactor,
+        )
+            .chain(),
+    );
     app.add_systems(
         Update,
         (
@@ -108,16 +170,42 @@ impl FromWorld for GameplayAssets {
     }
 }
 
-fn spawn_reactor(mut commands: Commands) {
+fn spawn_camera(mut commands: Commands) {
     commands.spawn((
         Name::new("Camera"),
         Camera2d,
         StateScoped(Screen::Gameplay),
         CrtSettings::default(),
     ));
+}
+
+/// Builds the model of a fresh run. Resuming a saved run replaces it before the core spawns.
+pub fn create_reactor(
+    mut commands: Commands,
+    layout: Res<ReactorLayout>,
+    balance: Res<Balance>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<SimulationRng>,
+) {
+    let balance = difficulty.apply(&balance).unwrap_or_else(|err| {
+        warn!(
+            "playing {} with the balance file as it is: {err}",
+            difficulty.label()
+        );
+        balance.clone()
+    });
+    commands.insert_resource(Reactor(ReactorState::new(
+        &layout,
+        &balance,
+        *difficulty,
+        &mut rng,
+    )));
+}
+
+pub fn spawn_reactor(mut commands: Commands, reactor: Res<Reactor>) {
     commands.spawn((
         Name::new("Reactor Core"),
-        ReactorCore::default(),
+        ReactorCore::new(reactor.layout.clone()),
         Visibility::default(),
         Transform::from_xyz(0., 0., 0.),
         StateScoped(Screen::Gameplay),
@@ -129,13 +217,13 @@ struct CellButton(Entity);
 
 fn on_add_reactor_core(
     trigger: Trigger<OnAdd, ReactorCore>,
-    core: Single<&ReactorCore>,
+    reactor: Res<Reactor>,
+    mut cores: Query<&mut ReactorCore>,
     _assets: Res<GameplayAssets>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let outer_size = CELL_OUTER_SIZE;
+) -> Result {
     let cell_mesh = meshes.add(Circle::new(CELL_RADIUS));
     let fuel_mesh = meshes.add(Circle::new(FUEL_ROD_RADIUS));
     let rod_mesh = meshes.add(Rectangle::from_length(CONTROL_ROD_RADIUS * 2.));
@@ -151,27 +239,26 @@ fn on_add_reactor_core(
         ..default()
     });
 
-    let mut cells = Vec::new();
-    for (index, pos) in core.iter_cell_positions().enumerate() {
+    let mut core = cores.get_mut(trigger.target())?;
+    for (index, cell) in reactor.cells.iter().enumerate() {
+        let pos = cell.position;
         let entity = commands
             .spawn((
                 Name::new(format!("Cell {}/{}", pos.x, pos.y)),
                 ChildOf(trigger.target()),
                 Cell(pos),
                 CellIndex(index),
-                Transform::from_xyz(
-                    (pos.x as f32) * outer_size,
-                    (pos.y as f32) * outer_size,
-                    8.0,
-                ),
+                Transform::from_translation(reactor.layout.translation(pos).extend(8.0)),
                 Visibility::Inherited,
             ))
             .id();
         commands.spawn((
             Name::new("Fuel rod"),
             ChildOf(entity),
-            Reactivity(0.5),
-            FuelRod::random(INITIAL_URANIUM_TO_XENON_RATIO),
+            Reactivity(cell.reactivity(&reactor.balance)),
+            Refuelling((cell.refuelling > 0).then_some(cell.loaded)),
+            CellIndex(index),
+            cell.fuel,
             Mesh2d(fuel_mesh.clone()),
             Pickable::IGNORE,
             Transform::from_xyz(0.0, 0.0, 20.0),
@@ -195,24 +282,24 @@ fn on_add_reactor_core(
             ))
             .observe(on_cell_pointer_over)
             .observe(on_cell_pointer_out)
-            .observe(on_click_add_water);
-        cells.push((pos, entity));
+            .observe(on_click_cell);
+        spawn_cell_particles(&mut commands, entity, cell);
+        core.insert_cell(pos, entity);
     }
 
-    for pos in core.iter_control_positions() {
+    for (index, control_rod) in reactor.control_rods.iter().enumerate() {
+        let pos = control_rod.position;
         let rod = commands
             .spawn((
                 Name::new(format!("Control rod {}/{}", pos.x, pos.y)),
                 ChildOf(trigger.target()),
                 ControlRod(pos),
-                ControlRodInsertion(1.0),
+                ControlRodIndex(index),
+                ControlRodInsertion(control_rod.insertion),
+                ControlRodTarget(control_rod.target),
                 Mesh2d(rod_mesh.clone()),
                 MeshMaterial2d(materials.add(CONTROL_ROD_COLOR_INSERTED)),
-                Transform::from_xyz(
-                    (pos.x as f32) * outer_size,
-                    (pos.y as f32) * outer_size,
-                    8.0,
-                ),
+                Transform::from_translation(reactor.layout.translation(pos).extend(8.0)),
                 Pickable::default(),
                 PlaysHoverSound,
                 PlaysClickSound,
@@ -221,6 +308,7 @@ fn on_add_reactor_core(
                 CollisionLayers::new(GameLayer::ControlRod, GameLayer::Neutron),
             ))
             .id();
+        core.insert_control_rod(pos, rod);
         commands.spawn((
             Name::new("Movement indicator"),
             ChildOf(rod),
@@ -235,7 +323,7 @@ fn on_add_reactor_core(
             Name::new("Insertion indicator"),
             ChildOf(rod),
             ControlRodInsertionIndicator,
-            Text2d::new("100%"),
+            Text2d::new(format!("{:.0}%", control_rod.insertion * 100.)),
             TextFont::from_font_size(18.),
             TextColor::WHITE,
             UseBoldFont,
@@ -243,6 +331,32 @@ fn on_add_reactor_core(
             Pickable::IGNORE,
         ));
     }
+    Ok(())
+}
+
+/// Spawns the particles of a cell that already has water or steam in it, e.g. in a resumed run.
+fn spawn_cell_particles(commands: &mut Commands, entity: Entity, cell: &CellState) {
+    for _ in 0..cell.water {
+        commands.spawn((
+            Name::new("Water particle"),
+            Particle::Water(false),
+            InCell,
+            TargetAngle::default(),
+            Transform::from_xyz(0.0, 0.0, 20.0),
+            ChildOf(entity),
+        ));
+    }
+    for _ in 0..cell.steam {
+        commands.spawn((
+            Name::new("Steam particle"),
+            Particle::Steam,
+            Lifetime(Stopwatch::new()),
+            InCell,
+            TargetAngle::default(),
+            Transform::from_xyz(0.0, 0.0, 20.0),
+            ChildOf(entity),
+        ));
+    }
 }
 
 fn on_cell_pointer_over(trigger: Trigger<Pointer<Over>>, mut commands: Commands) {
@@ -257,13 +371,36 @@ fn on_cell_pointer_out(trigger: Trigger<Pointer<Out>>, mut commands: Commands) {
         .try_insert(TargetScale(1.));
 }
 
-fn on_click_add_water(
+/// Adds water to the cell on left click and replaces its spent fuel on right click.
+///
+/// Clicks that end a box selection or come with a modifier key held belong to the control rods.
+fn on_click_cell(
     trigger: Trigger<Pointer<Click>>,
     mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    selecting: Query<(), With<banks::SelectionBox>>,
     query: Query<&CellButton>,
+    cells: Query<&CellIndex, With<Cell>>,
 ) -> Result {
+    let modifiers = [
+        KeyCode::ShiftLeft,
+        KeyCode::ShiftRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ];
+    if !selecting.is_empty() || keys.any_pressed(modifiers) {
+        return Ok(());
+    }
     let button = query.get(trigger.target())?;
-    commands.trigger_targets(FlowWaterParticlesIntoCell, button.0);
+    let cell = cells.get(button.0)?;
+    match trigger.event().button {
+        PointerButton::Secondary => commands.trigger(PlayerAction::Refuel { cell: cell.0 }),
+        _ => commands.trigger(PlayerAction::AddWater { cell: cell.0 }),
+    }
     Ok(())
 }
 
@@ -355,10 +492,15 @@ fn particle_movement(
 
 fn neutron_distance_movement(
     time: Res<Time>,
-    mut query: Query<&mut CurrentDistance, With<Neutron>>,
+    reactor: Res<Reactor>,
+    mut query: Query<(&mut CurrentDistance, &NeutronEnergy), With<Neutron>>,
 ) {
-    for mut current in &mut query {
-        let step = time.delta_secs() * NEUTRON_SPEED;
+    for (mut current, energy) in &mut query {
+        let speed = match energy {
+            NeutronEnergy::Fast => reactor.balance.fast_neutron_speed,
+            NeutronEnergy::Thermal => reactor.balance.neutron_speed,
+        };
+        let step = time.delta_secs() * speed;
         current.0 += step;
     }
 }
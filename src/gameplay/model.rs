@@ -0,0 +1,1414 @@
+//! A plain model of the reactor that owns the simulation rules.
+//!
+//! [`ReactorState`] knows nothing about entities. The ECS layer mirrors it into components and
+//! turns the [`ReactorEvent`]s returned from [`ReactorState::step`] into visuals, which keeps the
+//! rules reusable by other frontends such as the headless runner.
+
+use std::{collections::HashMap, f32::consts::TAU, time::Duration};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Balance, ControlRodMovement, Difficulty, FuelRod, NeutronEnergy, Phase, Position,
+    ReactorLayout, RngStream, SimulationRng,
+};
+use crate::screens::game_over::GameOverCause;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CellState {
+    pub position: Position,
+    pub fuel: FuelRod,
+    /// The fuel rod the player put in, which refuelling puts in again.
+    #[serde(default)]
+    pub loaded: FuelRod,
+    /// What fertile fuel in the rod has bred into, if it has.
+    #[serde(default)]
+    pub bred: Option<FuelRod>,
+    pub water: usize,
+    pub steam: usize,
+    #[serde(default)]
+    pub temperature: f32,
+    /// Number of fissions the fuel rod has gone through.
+    #[serde(default)]
+    pub burnup: usize,
+    /// Ticks left until a fresh fuel rod is in place, zero when the cell isn't being refuelled.
+    #[serde(default)]
+    pub refuelling: usize,
+    /// Iodine left behind by fission, which decays into xenon.
+    #[serde(default)]
+    pub iodine: f32,
+    /// Xenon in the fuel, which swallows neutrons. Once there's
+    /// [`Balance::xenon_poison_level`] of it the rod turns into [`FuelRod::Xenon`].
+    #[serde(default)]
+    pub xenon: f32,
+    /// Fission products that will release a neutron during a later
+    /// [`Phase::NeutronRelease`].
+    #[serde(default)]
+    pub precursors: usize,
+    /// Neutrons fertile fuel has caught towards breeding, see
+    /// [`super::FuelType::breeds_into`].
+    #[serde(default)]
+    pub breeding: usize,
+    #[serde(default)]
+    pub pump: PumpState,
+}
+
+impl CellState {
+    /// What the fuel rod is made of now. [`CellState::fuel`] shows this unless the fuel is
+    /// poisoned or spent.
+    pub fn material(&self) -> FuelRod {
+        self.bred.unwrap_or(self.loaded)
+    }
+
+    /// Total number of particles in the cell.
+    pub fn pressure(&self) -> usize {
+        self.water + self.steam
+    }
+
+    /// How readily the fuel releases and splits from neutrons, from 1 for fresh fuel down to
+    /// 0 for spent fuel.
+    pub fn reactivity(&self, balance: &Balance) -> f32 {
+        if self.fuel == FuelRod::Spent {
+            return 0.0;
+        }
+        let Some(fuel) = balance.fuels.get(self.material()) else {
+            return 1.0;
+        };
+        let burnup = self.burnup as f32 / fuel.burnup_fissions as f32;
+        1.0 - burnup.min(1.0) * balance.burnup_reactivity_loss
+    }
+}
+
+/// The coolant pump of a cell, which moves water from the water container into the cell during
+/// every [`Phase::WaterFlow`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PumpState {
+    /// Water particles moved per tick, zero when the pump is switched off.
+    pub rate: usize,
+    /// Ticks left until a tripped pump restarts, zero while it runs.
+    pub tripped: usize,
+}
+
+impl PumpState {
+    /// Whether the pump moves water and draws power.
+    pub fn is_running(&self) -> bool {
+        self.rate > 0 && self.tripped == 0
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ControlRodState {
+    pub position: Position,
+    pub insertion: f32,
+    /// Direction the rod is moving in, towards [`ControlRodState::target`].
+    pub movement: Option<ControlRodMovement>,
+    /// Insertion the rod is moving to and stops at.
+    #[serde(default)]
+    pub target: Option<f32>,
+}
+
+impl ControlRodState {
+    /// Starts moving the rod towards `target`, or stops it if it's already there.
+    pub fn set_target(&mut self, target: f32) {
+        let target = target.clamp(0.0, 1.0);
+        if target == self.insertion {
+            self.target = None;
+            self.movement = None;
+        } else {
+            self.target = Some(target);
+            self.movement = Some(if target > self.insertion {
+                ControlRodMovement::Up
+            } else {
+                ControlRodMovement::Down
+            });
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PowerDemandState {
+    pub demand: usize,
+    /// Amount added to the demand every [`Balance::increase_power_demand_sec`].
+    pub delta: usize,
+    pub tutorial_remaining: Duration,
+    pub demand_elapsed: Duration,
+    pub rate_elapsed: Duration,
+}
+
+impl PowerDemandState {
+    fn new(balance: &Balance) -> Self {
+        Self {
+            demand: 0,
+            delta: 1,
+            tutorial_remaining: Duration::from_secs_f32(balance.tutorial_sec),
+            demand_elapsed: Duration::ZERO,
+            rate_elapsed: Duration::ZERO,
+        }
+    }
+
+    fn advance(&mut self, delta: Duration, balance: &Balance) {
+        if !self.tutorial_remaining.is_zero() {
+            self.tutorial_remaining = self.tutorial_remaining.saturating_sub(delta);
+            if !self.tutorial_remaining.is_zero() {
+                return;
+            }
+        }
+
+        let demand_period = Duration::from_secs_f32(balance.increase_power_demand_sec);
+        self.demand_elapsed += delta;
+        while self.demand_elapsed >= demand_period {
+            self.demand_elapsed -= demand_period;
+            self.demand += self.delta;
+        }
+
+        let rate_period = Duration::from_secs_f32(balance.increase_power_demand_increase_rate_sec);
+        self.rate_elapsed += delta;
+        while self.rate_elapsed >= rate_period {
+            self.rate_elapsed -= rate_period;
+            self.delta *= 2;
+        }
+    }
+}
+
+/// The emergency shutdown, which drives every control rod all the way in and holds it there.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScramState {
+    /// Whether the shutdown is latched. It stays latched until the player resets it.
+    pub latched: bool,
+    /// Time left before a latched shutdown can be reset.
+    pub cooldown: Duration,
+    /// Number of shutdowns during the run.
+    pub count: usize,
+    /// Whether an auto-SCRAM condition has been met since it last cleared. The reactor only shuts
+    /// down on its own again once the condition has cleared.
+    #[serde(default)]
+    pub auto_tripped: bool,
+}
+
+/// The turbine and generator that turn steam into power.
+///
+/// Steam through the throttle valve drives the speed, which only follows it slowly. The generator
+/// is most efficient while the speed matches the load of the grid and the pumps, and never puts
+/// out more than the steam let in during a tick.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TurbineState {
+    /// Opening of the throttle valve, the fraction of [`Balance::turbine_valve_flow`] it lets
+    /// through.
+    pub throttle: f32,
+    /// Steam per tick the turbine spins with.
+    pub speed: f32,
+    /// Power the grid and the pumps asked for during the last tick.
+    pub load: usize,
+    /// Efficiency the generator ran at during the last tick.
+    pub efficiency: f32,
+    /// Ticks left until a tripped turbine restarts, zero while it runs.
+    pub tripped: usize,
+    /// Number of trips during the run.
+    pub trips: usize,
+}
+
+impl Default for TurbineState {
+    fn default() -> Self {
+        Self {
+            throttle: 0.2,
+            speed: 0.0,
+            load: 0,
+            efficiency: 0.0,
+            tripped: 0,
+            trips: 0,
+        }
+    }
+}
+
+impl TurbineState {
+    /// The speed measured against the load, 1 when the turbine carries exactly the load.
+    pub fn load_ratio(&self) -> f32 {
+        self.speed / self.load.max(1) as f32
+    }
+
+    /// Fraction of the steam's power the generator turns into power at `ratio` of the load.
+    pub fn efficiency_at(ratio: f32, balance: &Balance) -> f32 {
+        let off = ratio - 1.0;
+        (balance.turbine_peak_efficiency - balance.turbine_efficiency_falloff * off * off).max(0.0)
+    }
+}
+
+/// What happens to a neutron that hits a fuel rod.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NeutronHit {
+    /// The fuel split and released `prompt` neutrons right away. The rest of the
+    /// [`super::FuelType::neutron_yield`] were stored as precursors.
+    Fission {
+        prompt: usize,
+    },
+    Absorbed,
+    PassedThrough,
+}
+
+/// Something that happened during a [`ReactorState::step`] that a frontend may want to show.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReactorEvent {
+    NeutronReleased {
+        cell: usize,
+        angle: f32,
+    },
+    SteamVented {
+        cell: usize,
+        count: usize,
+    },
+    /// Water in a cell boiled from the heat of the cell alone.
+    WaterBoiled {
+        cell: usize,
+        count: usize,
+    },
+    /// The pump of a cell moved water from the water container into it.
+    WaterPumped {
+        cell: usize,
+        count: usize,
+    },
+    PowerGenerated(usize),
+    Failed(GameOverCause),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReactorState {
+    /// The layout the cells and control rods were created from.
+    #[serde(default)]
+    pub layout: ReactorLayout,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// The tuning values the rules run with, with [`ReactorState::difficulty`] applied.
+    pub balance: Balance,
+    /// The phase that will run on the next [`ReactorState::step`].
+    pub phase: Phase,
+    pub cells: Vec<CellState>,
+    pub control_rods: Vec<ControlRodState>,
+    pub water: usize,
+    /// Amount of water moved into a cell per player action.
+    pub water_flow: usize,
+    pub steam: usize,
+    pub energy: usize,
+    pub demand: PowerDemandState,
+    pub ticks_without_power: usize,
+    #[serde(default)]
+    pub scram: ScramState,
+    #[serde(default)]
+    pub turbine: TurbineState,
+    pub failure: Option<GameOverCause>,
+}
+
+impl ReactorState {
+    /// A fresh run on `difficulty`, which plays with `balance` as it's passed in, see
+    /// [`Difficulty::apply`].
+    pub fn new(
+        layout: &ReactorLayout,
+        balance: &Balance,
+        difficulty: Difficulty,
+        rng: &mut SimulationRng,
+    ) -> Self {
+        let balance = balance.clone();
+        let fuel_rng = rng.stream(RngStream::Fuel);
+        Self {
+            phase: Phase::default(),
+            cells: layout
+                .iter_cells()
+                .map(|(position, fuel)| {
+                    let fuel = fuel.unwrap_or_else(|| {
+                        FuelRod::random(fuel_rng, balance.initial_uranium_chance)
+                    });
+                    CellState {
+                        position,
+                        fuel,
+                        // Poisoned and spent rods in the layout are uranium underneath.
+                        loaded: match fuel {
+                            FuelRod::Xenon | FuelRod::Spent => FuelRod::Uranium,
+                            fuel => fuel,
+                        },
+                        water: 0,
+                        steam: 0,
+                        temperature: balance.ambient_temperature,
+                        burnup: 0,
+                        refuelling: 0,
+                        iodine: 0.0,
+                        xenon: if fuel == FuelRod::Xenon {
+                            balance.xenon_poison_level
+                        } else {
+                            0.0
+                        },
+                        precursors: 0,
+                        bred: None,
+                        breeding: 0,
+                        pump: PumpState::default(),
+                    }
+                })
+                .collect(),
+            control_rods: layout
+                .iter_control_positions()
+                .map(|position| ControlRodState {
+                    position,
+                    insertion: 1.0,
+                    movement: None,
+                    target: None,
+                })
+                .collect(),
+            water: balance.initial_water_stored,
+            water_flow: balance.initial_water_flow,
+            steam: 0,
+            energy: 0,
+            demand: PowerDemandState::new(&balance),
+            ticks_without_power: 0,
+            scram: ScramState::default(),
+            turbine: TurbineState::default(),
+            failure: None,
+            layout: layout.clone(),
+            difficulty,
+            balance,
+        }
+    }
+
+    /// Runs the current phase and advances to the next one.
+    ///
+    /// Does nothing once the reactor has failed.
+    pub fn step(&mut self, rng: &mut SimulationRng) -> Vec<ReactorEvent> {
+        let mut events = Vec::new();
+        if self.failure.is_some() {
+            return events;
+        }
+
+        match self.phase {
+            Phase::PowerGeneration => self.generate_power(&mut events),
+            Phase::WaterFlow => {
+                self.create_water();
+                self.pump_water(&mut events);
+            }
+            Phase::NeutronRelease => {
+                self.release_neutrons(rng.stream(RngStream::NeutronRelease), &mut events);
+                self.release_delayed_neutrons(rng.stream(RngStream::Precursors), &mut events);
+            }
+            Phase::SteamVenting => {
+                self.conduct_heat();
+                self.boil_hot_cells(&mut events);
+                self.vent_steam(&mut events);
+                self.decay_fission_products();
+                self.refuel_cells();
+                self.check_pumps();
+                self.check_auto_scram();
+                self.check_pressure();
+                self.check_temperature();
+            }
+        }
+
+        if let Some(cause) = self.failure {
+            events.push(ReactorEvent::Failed(cause));
+        }
+        self.phase = self.phase.next();
+        events
+    }
+
+    /// Advances everything that moves with time rather than once per phase by `delta` of
+    /// simulation time.
+    pub fn advance(&mut self, delta: Duration) {
+        self.demand.advance(delta, &self.balance);
+        self.scram.cooldown = self.scram.cooldown.saturating_sub(delta);
+        self.move_control_rods(delta);
+    }
+
+    /// Moves up to [`ReactorState::water_flow`] water particles from the water container into a
+    /// cell and returns how many were moved.
+    pub fn flow_water_into_cell(&mut self, cell: usize) -> usize {
+        let Some(cell) = self.cells.get_mut(cell) else {
+            return 0;
+        };
+        let count = self.water.min(self.water_flow);
+        self.water -= count;
+        cell.water += count;
+        count
+    }
+
+    pub fn increase_water_flow(&mut self) {
+        self.water_flow = (self.water_flow + 1).min(self.balance.max_water_flow);
+    }
+
+    pub fn decrease_water_flow(&mut self) {
+        self.water_flow = self.water_flow.saturating_sub(1).max(1);
+    }
+
+    /// Sets how much water the pump of a cell moves per tick, up to [`Balance::max_pump_rate`].
+    /// A tripped pump keeps the new rate for when it restarts.
+    pub fn set_pump_rate(&mut self, cell: usize, rate: usize) {
+        if let Some(cell) = self.cells.get_mut(cell) {
+            cell.pump.rate = rate.min(self.balance.max_pump_rate);
+        }
+    }
+
+    /// Opens or closes the throttle valve of the turbine.
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.turbine.throttle = throttle.clamp(0.0, 1.0);
+    }
+
+    /// Power the running pumps need for the next tick.
+    pub fn pump_power_draw(&self) -> usize {
+        let rate: usize = self
+            .cells
+            .iter()
+            .filter(|cell| cell.pump.is_running())
+            .map(|cell| cell.pump.rate)
+            .sum();
+        (rate as f32 * self.balance.pump_power_per_water).ceil() as usize
+    }
+
+    /// Turns a single water particle in a cell into steam, which carries away some of the heat
+    /// of the cell. Returns `false` if there was no water.
+    pub fn boil_water(&mut self, cell: usize) -> bool {
+        let Some(cell) = self.cells.get_mut(cell) else {
+            return false;
+        };
+        if cell.water == 0 {
+            return false;
+        }
+        cell.water -= 1;
+        cell.steam += self.balance.steam_generated_per_water;
+        cell.temperature =
+            (cell.temperature - self.balance.heat_per_boil).max(self.balance.ambient_temperature);
+        true
+    }
+
+    /// Handles a neutron hitting the fuel rod of a cell. Fuel splits according to its
+    /// [`super::FuelType`] and the energy of the neutron, and less readily the more it has
+    /// burned up. Xenon in the fuel may swallow the neutron first, which burns it away.
+    pub fn absorb_neutron(
+        &mut self,
+        cell: usize,
+        energy: NeutronEnergy,
+        rng: &mut SimulationRng,
+    ) -> NeutronHit {
+        let Some(cell) = self.cells.get_mut(cell) else {
+            return NeutronHit::PassedThrough;
+        };
+        if cell.fuel != FuelRod::Spent && cell.xenon > 0.0 {
+            let capture_chance = (cell.xenon * self.balance.xenon_capture_chance).min(1.0);
+            if rng.stream(RngStream::Xenon).random_range(0.0..1.0) < capture_chance {
+                cell.xenon = (cell.xenon - 1.0).max(0.0);
+                if cell.fuel == FuelRod::Xenon && cell.xenon < self.balance.xenon_poison_level {
+                    cell.fuel = cell.material();
+                }
+                return NeutronHit::Absorbed;
+            }
+        }
+
+        let fuel = match cell.fuel {
+            // Poisoned fuel can't split, the neutron goes through if the xenon missed it.
+            FuelRod::Xenon => return NeutronHit::PassedThrough,
+            // Fission products in spent fuel swallow neutrons.
+            FuelRod::Spent => return NeutronHit::Absorbed,
+            fuel => fuel,
+        };
+        let Some(fuel_type) = self.balance.fuels.get(fuel) else {
+            return NeutronHit::PassedThrough;
+        };
+
+        let chance = match energy {
+            NeutronEnergy::Fast => fuel_type.fast_fission_chance,
+            NeutronEnergy::Thermal => fuel_type.thermal_fission_chance,
+        } * cell.reactivity(&self.balance);
+        let rng = rng.stream(RngStream::Fission);
+        if rng.random_range(0.0..1.0) < chance {
+            cell.temperature += self.balance.heat_per_fission;
+            cell.iodine += self.balance.iodine_per_fission;
+            cell.burnup += 1;
+            if cell.burnup >= fuel_type.burnup_fissions {
+                cell.fuel = FuelRod::Spent;
+            }
+            let delayed = (0..fuel_type.neutron_yield)
+                .filter(|_| rng.random_range(0.0..1.0) < self.balance.delayed_neutron_fraction)
+                .count();
+            cell.precursors += delayed;
+            NeutronHit::Fission {
+                prompt: fuel_type.neutron_yield - delayed,
+            }
+        } else if rng.random_range(0.0..1.0) < fuel_type.absorption_chance {
+            if let Some(bred) = fuel_type.breeds_into {
+                cell.breeding += 1;
+                if cell.breeding >= fuel_type.breeding_captures {
+                    cell.fuel = bred;
+                    cell.bred = Some(bred);
+                    cell.breeding = 0;
+                    cell.burnup = 0;
+                }
+            }
+            NeutronHit::Absorbed
+        } else {
+            NeutronHit::PassedThrough
+        }
+    }
+
+    /// Starts replacing the spent fuel rod of a cell with a fresh one, which takes
+    /// [`Balance::refuel_ticks`]. Returns `false` if the fuel isn't spent or is already being
+    /// replaced.
+    pub fn refuel(&mut self, cell: usize) -> bool {
+        let Some(cell) = self.cells.get_mut(cell) else {
+            return false;
+        };
+        if cell.fuel != FuelRod::Spent || cell.refuelling > 0 {
+            return false;
+        }
+        cell.refuelling = self.balance.refuel_ticks.max(1);
+        true
+    }
+
+    /// Whether a neutron hitting a control rod gets absorbed by it.
+    pub fn control_rod_absorbs(&self, rod: usize, rng: &mut SimulationRng) -> bool {
+        let Some(rod) = self.control_rods.get(rod) else {
+            return false;
+        };
+        rng.stream(RngStream::ControlRods).random_range(0.0..1.0) < rod.insertion
+    }
+
+    /// Starts moving a control rod towards the opposite end, or reverses it if it's moving.
+    ///
+    /// Does nothing while a SCRAM is latched.
+    pub fn toggle_control_rod(&mut self, rod: usize) {
+        if self.scram.latched {
+            return;
+        }
+        let Some(rod) = self.control_rods.get_mut(rod) else {
+            return;
+        };
+        let movement = match rod.movement {
+            Some(mut movement) => {
+                movement.reverse();
+                movement
+            }
+            None if rod.insertion > 0.5 => ControlRodMovement::Down,
+            None => ControlRodMovement::Up,
+        };
+        rod.set_target(movement.end());
+    }
+
+    /// Starts moving a control rod towards `target` insertion.
+    ///
+    /// Does nothing while a SCRAM is latched.
+    pub fn set_control_rod_target(&mut self, rod: usize, target: f32) {
+        if self.scram.latched {
+            return;
+        }
+        if let Some(rod) = self.control_rods.get_mut(rod) {
+            rod.set_target(target);
+        }
+    }
+
+    /// Shuts the reactor down: every control rod is driven all the way in at
+    /// [`Balance::scram_insertion_speed`] and held there until [`ReactorState::reset_scram`].
+    ///
+    /// The grid makes up for the lost power in the meantime, so the reactor can't fail for lack
+    /// of power while the SCRAM is latched, but the demand grows by
+    /// [`Balance::scram_demand_penalty`] for every shutdown.
+    pub fn scram(&mut self) {
+        if self.scram.latched {
+            return;
+        }
+        self.scram.latched = true;
+        self.scram.cooldown = Duration::from_secs_f32(self.balance.scram_cooldown_sec);
+        self.scram.count += 1;
+        self.demand.demand += self.balance.scram_demand_penalty;
+        for rod in &mut self.control_rods {
+            rod.set_target(1.0);
+        }
+    }
+
+    /// Releases a latched SCRAM once its cooldown is over. Returns `false` if it couldn't.
+    pub fn reset_scram(&mut self) -> bool {
+        if !self.scram.latched || !self.scram.cooldown.is_zero() {
+            return false;
+        }
+        self.scram.latched = false;
+        true
+    }
+
+    fn move_control_rods(&mut self, delta: Duration) {
+        let speed = if self.scram.latched {
+            self.balance.scram_insertion_speed
+        } else {
+            self.balance.control_rod_insertion_speed
+        };
+        let step = delta.as_secs_f32() * speed;
+        for rod in &mut self.control_rods {
+            let Some(movement) = rod.movement else {
+                continue;
+            };
+            // Rods in runs saved before setpoints existed move all the way.
+            let target = rod.target.unwrap_or(movement.end());
+
+            if (target - rod.insertion).abs() <= step {
+                rod.insertion = target;
+                rod.movement = None;
+                rod.target = None;
+            } else {
+                rod.insertion += match movement {
+                    ControlRodMovement::Up => step,
+                    ControlRodMovement::Down => -step,
+                };
+            }
+        }
+    }
+
+    /// Lets steam through the throttle valve into the turbine and the generator turn its speed
+    /// into power. The running pumps take their share of the power first, and trip if there isn't
+    /// enough for all of them.
+    fn generate_power(&mut self, events: &mut Vec<ReactorEvent>) {
+        let draw = self.pump_power_draw();
+        let diff = self.demand.demand.saturating_sub(self.energy);
+        let load = diff + self.demand.delta + draw;
+
+        let mut count = self.spin_turbine(load);
+        if count < draw {
+            self.trip_running_pumps();
+        } else {
+            count -= draw;
+        }
+        self.energy += count;
+        if count > 0 {
+            events.push(ReactorEvent::PowerGenerated(count));
+        }
+
+        // The grid waits for the reactor while a SCRAM cools down, but not for longer.
+        if !self.scram.cooldown.is_zero() {
+            self.ticks_without_power = 0;
+        } else if diff > count {
+            self.ticks_without_power += 1;
+        } else {
+            self.ticks_without_power = 0;
+        }
+        if self.ticks_without_power > self.balance.power_failure_ticks {
+            self.failure = Some(GameOverCause::NotEnoughPower);
+        }
+    }
+
+    fn create_water(&mut self) {
+        self.water =
+            (self.water + self.balance.water_created_per_tick).min(self.balance.max_water_stored);
+    }
+
+    /// Advances the turbine by a tick against `load` and returns the power it generated.
+    fn spin_turbine(&mut self, load: usize) -> usize {
+        let balance = &self.balance;
+        let turbine = &mut self.turbine;
+        turbine.load = load;
+        if turbine.tripped > 0 {
+            turbine.tripped -= 1;
+            turbine.speed *= balance.turbine_inertia;
+            turbine.efficiency = 0.0;
+            return 0;
+        }
+
+        let admitted =
+            (self.steam as f32 * balance.turbine_valve_flow * turbine.throttle).ceil() as usize;
+        let admitted = admitted.min(self.steam);
+
+        let previous = turbine.speed;
+        turbine.speed = turbine.speed * balance.turbine_inertia
+            + admitted as f32 * (1.0 - balance.turbine_inertia);
+        let ratio = turbine.load_ratio();
+        turbine.efficiency = TurbineState::efficiency_at(ratio, balance);
+
+        // The turbine trips when the steam races it past what it's built for, or falls away under
+        // a turbine that was running.
+        if turbine.speed > balance.turbine_rated_speed * balance.turbine_overspeed
+            || (previous >= balance.turbine_min_steam && turbine.speed < balance.turbine_min_steam)
+        {
+            turbine.tripped = balance.turbine_restart_ticks;
+            turbine.trips += 1;
+            turbine.efficiency = 0.0;
+            return 0;
+        }
+
+        // Steam the load doesn't call for stays in the container.
+        let used = turbine.speed.min(load as f32).min(admitted as f32);
+        self.steam -= (used.ceil() as usize).min(admitted);
+        (used * turbine.efficiency).round() as usize
+    }
+
+    /// Lets every running pump move water from the water container into its cell, in cell order
+    /// while the water lasts.
+    fn pump_water(&mut self, events: &mut Vec<ReactorEvent>) {
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            if !cell.pump.is_running() {
+                continue;
+            }
+            let count = self.water.min(cell.pump.rate);
+            if count == 0 {
+                continue;
+            }
+            self.water -= count;
+            cell.water += count;
+            events.push(ReactorEvent::WaterPumped { cell: index, count });
+        }
+    }
+
+    fn trip_running_pumps(&mut self) {
+        for cell in &mut self.cells {
+            if cell.pump.is_running() {
+                cell.pump.tripped = self.balance.pump_restart_ticks;
+            }
+        }
+    }
+
+    /// Restarts tripped pumps once they've waited long enough and trips the ones pushing against
+    /// too much pressure.
+    fn check_pumps(&mut self) {
+        for cell in &mut self.cells {
+            if cell.pump.tripped > 0 {
+                cell.pump.tripped -= 1;
+            } else if cell.pump.is_running() && cell.pressure() > self.balance.pump_trip_pressure {
+                cell.pump.tripped = self.balance.pump_restart_ticks;
+            }
+        }
+    }
+
+    fn release_neutrons(&mut self, rng: &mut impl Rng, events: &mut Vec<ReactorEvent>) {
+        for (index, cell) in self.cells.iter().enumerate() {
+            let Some(fuel) = self.balance.fuels.get(cell.fuel) else {
+                continue;
+            };
+
+            let chance = fuel.spontaneous_release_chance * cell.reactivity(&self.balance);
+            for _ in 0..self.balance.max_neutrons_released_per_tick {
+                if rng.random_range(0.0..1.0) < chance {
+                    events.push(ReactorEvent::NeutronReleased {
+                        cell: index,
+                        angle: rng.random_range(0.0..TAU),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Moves heat between neighbouring cells and lets every cell lose a little to its
+    /// surroundings.
+    fn conduct_heat(&mut self) {
+        let indices: HashMap<_, _> = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (cell.position, index))
+            .collect();
+        let temperatures: Vec<_> = self.cells.iter().map(|cell| cell.temperature).collect();
+
+        let ambient = self.balance.ambient_temperature;
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            let flow: f32 = self
+                .layout
+                .lattice
+                .neighbours(cell.position)
+                .into_iter()
+                .filter_map(|pos| indices.get(&pos))
+                .map(|&neighbour| temperatures[neighbour] - temperatures[index])
+                .sum();
+            cell.temperature += flow * self.balance.heat_conduction_per_tick;
+            cell.temperature -= (cell.temperature - ambient) * self.balance.heat_loss_per_tick;
+        }
+    }
+
+    /// Boils water in cells above [`Balance::boiling_temperature`], more of it the hotter the
+    /// cell is.
+    fn boil_hot_cells(&mut self, events: &mut Vec<ReactorEvent>) {
+        for index in 0..self.cells.len() {
+            let mut count = 0;
+            while count < self.balance.max_water_boiled_per_tick
+                && self.cells[index].temperature >= self.balance.boiling_temperature
+                && self.boil_water(index)
+            {
+                count += 1;
+            }
+            if count > 0 {
+                events.push(ReactorEvent::WaterBoiled { cell: index, count });
+            }
+        }
+    }
+
+    /// Lets precursors decay, each releasing a neutron from its fuel rod.
+    fn release_delayed_neutrons(&mut self, rng: &mut impl Rng, events: &mut Vec<ReactorEvent>) {
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            let decayed = (0..cell.precursors)
+                .filter(|_| rng.random_range(0.0..1.0) < self.balance.precursor_decay_chance)
+                .count();
+            cell.precursors -= decayed;
+            for _ in 0..decayed {
+                events.push(ReactorEvent::NeutronReleased {
+                    cell: index,
+                    angle: rng.random_range(0.0..TAU),
+                });
+            }
+        }
+    }
+
+    fn vent_steam(&mut self, events: &mut Vec<ReactorEvent>) {
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            let count = cell.steam.min(self.balance.steam_vented_per_tick);
+            if count == 0 {
+                continue;
+            }
+            cell.steam -= count;
+            self.steam += count;
+            events.push(ReactorEvent::SteamVented { cell: index, count });
+        }
+    }
+
+    /// Decays iodine into xenon and xenon into harmless products, poisoning fuel with too much
+    /// xenon and reviving it once enough has decayed.
+    fn decay_fission_products(&mut self) {
+        for cell in &mut self.cells {
+            let decayed_iodine = cell.iodine * self.balance.iodine_decay_per_tick;
+            cell.iodine -= decayed_iodine;
+            cell.xenon += decayed_iodine - cell.xenon * self.balance.xenon_decay_per_tick;
+
+            let poisoned = cell.xenon >= self.balance.xenon_poison_level;
+            match cell.fuel {
+                FuelRod::Xenon if !poisoned => cell.fuel = cell.material(),
+                FuelRod::Xenon | FuelRod::Spent => {}
+                _ if poisoned => cell.fuel = FuelRod::Xenon,
+                _ => {}
+            }
+        }
+    }
+
+    fn refuel_cells(&mut self) {
+        for cell in &mut self.cells {
+            if cell.refuelling == 0 {
+                continue;
+            }
+            cell.refuelling -= 1;
+            if cell.refuelling == 0 {
+                cell.fuel = cell.loaded;
+                cell.bred = None;
+                cell.burnup = 0;
+                cell.breeding = 0;
+                cell.iodine = 0.0;
+                cell.xenon = 0.0;
+                cell.precursors = 0;
+            }
+        }
+    }
+
+    fn check_auto_scram(&mut self) {
+        let auto = &self.balance.auto_scram;
+        let triggered = self.cells.iter().any(|cell| {
+            auto.pressure.is_some_and(|level| cell.pressure() > level)
+                || auto
+                    .temperature
+                    .is_some_and(|temperature| cell.temperature > temperature)
+        });
+        if triggered && !self.scram.auto_tripped {
+            self.scram();
+        }
+        self.scram.auto_tripped = triggered;
+    }
+
+    fn check_pressure(&mut self) {
+        if self
+            .cells
+            .iter()
+            .any(|cell| cell.pressure() > self.balance.pressure_explosion_level)
+        {
+            self.failure = Some(GameOverCause::Explosion);
+        }
+    }
+
+    fn check_temperature(&mut self) {
+        if self
+            .cells
+            .iter()
+            .any(|cell| cell.temperature > self.balance.overheat_temperature)
+        {
+            self.failure = Some(GameOverCause::Overheating);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Length of a simulation tick in the game.
+    const TICK: Duration = Duration::from_millis(500);
+
+    fn reactor(seed: u64) -> (ReactorState, SimulationRng) {
+        let mut rng = SimulationRng::new(seed);
+        let reactor = ReactorState::new(
+            &ReactorLayout::default(),
+            &Balance::default(),
+            Difficulty::Normal,
+            &mut rng,
+        );
+        (reactor, rng)
+    }
+
+    /// Puts a fresh `fuel` rod into `cell`.
+    fn load(reactor: &mut ReactorState, cell: usize, fuel: FuelRod) {
+        let cell = &mut reactor.cells[cell];
+        cell.fuel = fuel;
+        cell.loaded = fuel;
+        cell.bred = None;
+        cell.burnup = 0;
+    }
+
+    fn run_phase(
+        reactor: &mut ReactorState,
+        rng: &mut SimulationRng,
+        phase: Phase,
+    ) -> Vec<ReactorEvent> {
+        reactor.phase = phase;
+        reactor.step(rng)
+    }
+
+    #[test]
+    fn step_runs_the_phases_in_order() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.cells[0].steam = 2;
+        let phases = [
+            Phase::PowerGeneration,
+            Phase::WaterFlow,
+            Phase::NeutronRelease,
+            Phase::SteamVenting,
+            Phase::PowerGeneration,
+        ];
+        for pair in phases.windows(2) {
+            let (water, steam) = (reactor.water, reactor.steam);
+            assert_eq!(reactor.phase, pair[0]);
+            reactor.step(&mut rng);
+            assert_eq!(reactor.phase, pair[1]);
+
+            // Water only comes in while it flows, and steam only leaves the cells while venting.
+            if pair[0] == Phase::WaterFlow {
+                assert_eq!(
+                    reactor.water,
+                    water + reactor.balance.water_created_per_tick
+                );
+            } else {
+                assert_eq!(reactor.water, water);
+            }
+            if pair[0] == Phase::SteamVenting {
+                assert_eq!(reactor.steam, steam + 2);
+            } else {
+                assert_eq!(reactor.steam, steam);
+            }
+        }
+    }
+
+    #[test]
+    fn power_shortage_fails_after_the_countdown() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.demand.demand = 10;
+        for ticks in 1..=reactor.balance.power_failure_ticks {
+            let events = run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+            assert!(events.is_empty());
+            assert_eq!(reactor.ticks_without_power, ticks);
+            assert_eq!(reactor.failure, None);
+        }
+
+        let events = run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert_eq!(reactor.failure, Some(GameOverCause::NotEnoughPower));
+        assert_eq!(
+            events,
+            vec![ReactorEvent::Failed(GameOverCause::NotEnoughPower)]
+        );
+        assert!(reactor.step(&mut rng).is_empty());
+    }
+
+    #[test]
+    fn generated_power_resets_the_countdown() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.demand.demand = 2;
+        reactor.ticks_without_power = 5;
+        reactor.steam = 100;
+
+        let events = run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert!(matches!(
+            events.as_slice(),
+            [ReactorEvent::PowerGenerated(count)] if *count == reactor.energy
+        ));
+        assert!(reactor.energy >= reactor.demand.demand);
+        assert_eq!(reactor.ticks_without_power, 0);
+    }
+
+    #[test]
+    fn venting_moves_steam_out_of_the_cells() {
+        let (mut reactor, mut rng) = reactor(1);
+        let vented = reactor.balance.steam_vented_per_tick;
+        reactor.cells[0].steam = vented + 2;
+
+        let events = run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert_eq!(reactor.cells[0].steam, 2);
+        assert_eq!(reactor.steam, vented);
+        assert!(events.contains(&ReactorEvent::SteamVented {
+            cell: 0,
+            count: vented
+        }));
+    }
+
+    #[test]
+    fn overpressure_explodes() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.cells[0].water = reactor.balance.pressure_explosion_level;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert_eq!(reactor.failure, None);
+
+        reactor.cells[0].water += 1;
+        let events = run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert_eq!(reactor.failure, Some(GameOverCause::Explosion));
+        assert_eq!(
+            events.last(),
+            Some(&ReactorEvent::Failed(GameOverCause::Explosion))
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_steps() {
+        let run = |seed| {
+            let (mut reactor, mut rng) = reactor(seed);
+            let events: Vec<_> = (0..400)
+                .flat_map(|_| {
+                    reactor.advance(TICK);
+                    reactor.step(&mut rng)
+                })
+                .collect();
+            let fuel: Vec<_> = reactor.cells.iter().map(|cell| cell.fuel).collect();
+            (events, fuel)
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn fuel_burns_up_and_is_refuelled() {
+        let (mut reactor, mut rng) = reactor(1);
+        let balance = &mut reactor.balance;
+        balance.xenon_capture_chance = 0.0;
+        balance.burnup_reactivity_loss = 0.0;
+        balance.fuels.uranium.thermal_fission_chance = 1.0;
+        balance.fuels.uranium.burnup_fissions = 3;
+        load(&mut reactor, 0, FuelRod::Uranium);
+        assert!(!reactor.refuel(0));
+
+        for _ in 0..3 {
+            let hit = reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng);
+            assert!(matches!(hit, NeutronHit::Fission { .. }));
+        }
+        assert_eq!(reactor.cells[0].fuel, FuelRod::Spent);
+        assert_eq!(reactor.cells[0].reactivity(&reactor.balance), 0.0);
+        assert_eq!(
+            reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+            NeutronHit::Absorbed
+        );
+
+        assert!(reactor.refuel(0));
+        assert!(!reactor.refuel(0));
+        for _ in 1..reactor.balance.refuel_ticks {
+            run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+            assert_eq!(reactor.cells[0].fuel, FuelRod::Spent);
+        }
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert_eq!(reactor.cells[0].fuel, FuelRod::Uranium);
+        assert_eq!(reactor.cells[0].burnup, 0);
+        assert_eq!(reactor.cells[0].refuelling, 0);
+    }
+
+    #[test]
+    fn burnup_lowers_reactivity() {
+        let (mut reactor, _) = reactor(1);
+        load(&mut reactor, 0, FuelRod::Uranium);
+        assert_eq!(reactor.cells[0].reactivity(&reactor.balance), 1.0);
+
+        reactor.cells[0].burnup = reactor.balance.fuels.uranium.burnup_fissions;
+        assert_eq!(
+            reactor.cells[0].reactivity(&reactor.balance),
+            1.0 - reactor.balance.burnup_reactivity_loss
+        );
+    }
+
+    #[test]
+    fn xenon_poisons_the_fuel_until_it_decays() {
+        let (mut reactor, mut rng) = reactor(1);
+        load(&mut reactor, 0, FuelRod::Uranium);
+        reactor.cells[0].xenon = reactor.balance.xenon_poison_level;
+        reactor.cells[0].iodine = 100.0;
+
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(reactor.cells[0].iodine < 100.0);
+        assert!(reactor.cells[0].xenon > reactor.balance.xenon_poison_level);
+        assert_eq!(reactor.cells[0].fuel, FuelRod::Xenon);
+
+        reactor.cells[0].iodine = 0.0;
+        reactor.cells[0].xenon = 1.0;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert_eq!(reactor.cells[0].fuel, FuelRod::Uranium);
+    }
+
+    #[test]
+    fn xenon_swallows_neutrons() {
+        let (mut reactor, mut rng) = reactor(1);
+        load(&mut reactor, 0, FuelRod::Uranium);
+        reactor.balance.xenon_capture_chance = 1.0;
+        reactor.cells[0].xenon = 2.0;
+        assert_eq!(
+            reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+            NeutronHit::Absorbed
+        );
+        assert_eq!(reactor.cells[0].xenon, 1.0);
+
+        // Poisoned fuel lets the neutrons the xenon missed through.
+        reactor.balance.xenon_capture_chance = 0.0;
+        reactor.cells[0].fuel = FuelRod::Xenon;
+        assert_eq!(
+            reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+            NeutronHit::PassedThrough
+        );
+    }
+
+    #[test]
+    fn fission_holds_back_delayed_neutrons() {
+        let (mut reactor, mut rng) = reactor(1);
+        let neutron_yield = reactor.balance.fuels.uranium.neutron_yield;
+        reactor.balance.xenon_capture_chance = 0.0;
+        reactor.balance.burnup_reactivity_loss = 0.0;
+        reactor.balance.fuels.uranium.spontaneous_release_chance = 0.0;
+        load(&mut reactor, 0, FuelRod::Uranium);
+
+        reactor.balance.delayed_neutron_fraction = 0.0;
+        assert_eq!(
+            reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+            NeutronHit::Fission {
+                prompt: neutron_yield
+            }
+        );
+        assert_eq!(reactor.cells[0].precursors, 0);
+
+        reactor.balance.delayed_neutron_fraction = 1.0;
+        assert_eq!(
+            reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+            NeutronHit::Fission { prompt: 0 }
+        );
+        assert_eq!(reactor.cells[0].precursors, neutron_yield);
+
+        reactor.balance.precursor_decay_chance = 1.0;
+        let events = run_phase(&mut reactor, &mut rng, Phase::NeutronRelease);
+        let released = events
+            .iter()
+            .filter(|event| matches!(event, ReactorEvent::NeutronReleased { cell: 0, .. }))
+            .count();
+        assert_eq!(released, neutron_yield);
+        assert_eq!(reactor.cells[0].precursors, 0);
+    }
+
+    #[test]
+    fn fertile_fuel_breeds_and_is_refuelled_as_loaded() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.balance.xenon_capture_chance = 0.0;
+        reactor.balance.fuels.thorium.absorption_chance = 1.0;
+        let captures = reactor.balance.fuels.thorium.breeding_captures;
+        load(&mut reactor, 0, FuelRod::Thorium);
+
+        for _ in 0..captures {
+            assert_eq!(reactor.cells[0].fuel, FuelRod::Thorium);
+            assert_eq!(
+                reactor.absorb_neutron(0, NeutronEnergy::Thermal, &mut rng),
+                NeutronHit::Absorbed
+            );
+        }
+        let cell = &reactor.cells[0];
+        assert_eq!(cell.fuel, FuelRod::Uranium);
+        assert_eq!(cell.material(), FuelRod::Uranium);
+        assert_eq!(cell.loaded, FuelRod::Thorium);
+
+        reactor.cells[0].fuel = FuelRod::Spent;
+        assert!(reactor.refuel(0));
+        for _ in 0..reactor.balance.refuel_ticks {
+            run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        }
+        let cell = &reactor.cells[0];
+        assert_eq!(cell.fuel, FuelRod::Thorium);
+        assert_eq!(cell.bred, None);
+    }
+
+    #[test]
+    fn scram_holds_the_power_countdown_only_while_it_cools_down() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.demand.demand = 10;
+        reactor.control_rods[0].insertion = 0.0;
+        reactor.scram();
+        assert!(reactor.scram.latched);
+        assert_eq!(
+            reactor.demand.demand,
+            10 + reactor.balance.scram_demand_penalty
+        );
+        assert_eq!(reactor.control_rods[0].target, Some(1.0));
+        reactor.set_control_rod_target(0, 0.0);
+        assert_eq!(reactor.control_rods[0].target, Some(1.0));
+
+        for _ in 0..reactor.balance.power_failure_ticks * 2 {
+            run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+            assert_eq!(reactor.ticks_without_power, 0);
+        }
+        assert!(!reactor.reset_scram());
+
+        reactor.advance(Duration::from_secs_f32(reactor.balance.scram_cooldown_sec));
+        run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert_eq!(reactor.ticks_without_power, 1);
+        assert!(reactor.scram.latched);
+        assert!(reactor.reset_scram());
+        assert!(!reactor.scram.latched);
+    }
+
+    #[test]
+    fn auto_scram_triggers_again_only_once_cleared() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.balance.auto_scram.pressure = Some(10);
+        reactor.cells[0].water = 11;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(reactor.scram.latched);
+        assert_eq!(reactor.scram.count, 1);
+
+        reactor.advance(Duration::from_secs_f32(reactor.balance.scram_cooldown_sec));
+        assert!(reactor.reset_scram());
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(!reactor.scram.latched);
+
+        reactor.cells[0].water = 0;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        reactor.cells[0].water = 11;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(reactor.scram.latched);
+        assert_eq!(reactor.scram.count, 2);
+    }
+
+    #[test]
+    fn pumps_feed_their_cells_while_the_water_lasts() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.balance.water_created_per_tick = 0;
+        reactor.water = 7;
+        reactor.set_pump_rate(0, 5);
+        reactor.set_pump_rate(1, 5);
+        reactor.set_pump_rate(2, 100);
+        assert_eq!(reactor.cells[2].pump.rate, reactor.balance.max_pump_rate);
+
+        let events = run_phase(&mut reactor, &mut rng, Phase::WaterFlow);
+        assert_eq!(
+            events,
+            vec![
+                ReactorEvent::WaterPumped { cell: 0, count: 5 },
+                ReactorEvent::WaterPumped { cell: 1, count: 2 },
+            ]
+        );
+        assert_eq!(reactor.water, 0);
+        assert_eq!(reactor.cells[0].water, 5);
+        assert_eq!(reactor.cells[1].water, 2);
+        assert_eq!(reactor.cells[2].water, 0);
+    }
+
+    #[test]
+    fn pumps_trip_without_power_and_restart() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.set_pump_rate(0, 4);
+        assert!(reactor.pump_power_draw() > 0);
+
+        run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert_eq!(
+            reactor.cells[0].pump.tripped,
+            reactor.balance.pump_restart_ticks
+        );
+        assert_eq!(reactor.pump_power_draw(), 0);
+
+        for _ in 0..reactor.balance.pump_restart_ticks {
+            run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        }
+        assert!(reactor.cells[0].pump.is_running());
+        assert_eq!(reactor.cells[0].pump.rate, 4);
+    }
+
+    #[test]
+    fn pumps_trip_against_pressure() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.set_pump_rate(0, 4);
+        reactor.cells[0].water = reactor.balance.pump_trip_pressure;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(reactor.cells[0].pump.is_running());
+
+        reactor.cells[0].water += 1;
+        run_phase(&mut reactor, &mut rng, Phase::SteamVenting);
+        assert!(!reactor.cells[0].pump.is_running());
+    }
+
+    #[test]
+    fn turbine_makes_no_more_power_than_the_steam_it_uses() {
+        for steam in [1, 5, 20, 50] {
+            let (mut reactor, mut rng) = reactor(1);
+            reactor.set_throttle(1.0);
+            reactor.turbine.speed = reactor.balance.turbine_rated_speed;
+            reactor.demand.demand = 1000;
+            reactor.steam = steam;
+
+            run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+            let used = steam - reactor.steam;
+            assert!(used > 0);
+            assert!(reactor.energy <= used);
+        }
+    }
+
+    #[test]
+    fn turbine_leaves_the_steam_it_does_not_need() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.set_throttle(1.0);
+        reactor.turbine.speed = 10.0;
+        reactor.demand.demand = 2;
+        reactor.steam = 20;
+
+        run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert_eq!(reactor.turbine.trips, 0);
+        assert_eq!(reactor.steam, 20 - reactor.turbine.load);
+    }
+
+    #[test]
+    fn turbine_runs_on_while_the_grid_needs_nothing() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.set_throttle(1.0);
+        reactor.demand.demand = 0;
+        for _ in 0..20 {
+            reactor.steam = 10;
+            run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        }
+        assert_eq!(reactor.turbine.trips, 0);
+        assert!(reactor.turbine.speed > 0.0);
+    }
+
+    #[test]
+    fn turbine_trips_on_overspeed_and_restarts() {
+        let (mut reactor, mut rng) = reactor(1);
+        reactor.set_throttle(1.0);
+        reactor.steam = 1000;
+
+        run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+        assert_eq!(reactor.energy, 0);
+        assert_eq!(reactor.turbine.trips, 1);
+        assert_eq!(
+            reactor.turbine.tripped,
+            reactor.balance.turbine_restart_ticks
+        );
+
+        reactor.steam = 0;
+        for _ in 0..reactor.balance.turbine_restart_ticks {
+            run_phase(&mut reactor, &mut rng, Phase::PowerGeneration);
+            assert_eq!(reactor.energy, 0);
+        }
+        assert_eq!(reactor.turbine.tripped, 0);
+    }
+
+    #[test]
+    fn turbine_runs_best_at_its_load() {
+        let balance = Balance::default();
+        let peak = TurbineState::efficiency_at(1.0, &balance);
+        assert_eq!(peak, balance.turbine_peak_efficiency);
+        assert!(TurbineState::efficiency_at(0.5, &balance) < peak);
+        assert!(TurbineState::efficiency_at(1.5, &balance) < peak);
+    }
+}
@@ -1,6 +1,5 @@
 use avian2d::prelude::CollisionStarted;
 use bevy::{platform::collections::HashSet, prelude::*};
-use rand::Rng;
 
 use crate::{PausableSystems, screens::Screen};
 
@@ -16,7 +15,10 @@ pub fn plugin(app: &mut App) {
             .run_if(in_state(Screen::Gameplay))
             .in_set(PausableSystems),
     );
-    app.add_systems(Update, handle_neutron_collisions);
+    app.add_systems(
+        Update,
+        (handle_neutron_collisions, update_neutron_colors).run_if(in_state(Screen::Gameplay)),
+    );
 }
 
 #[derive(Event, Reflect, Copy, Clone, Debug)]
@@ -28,13 +30,15 @@ pub struct NeutronCollision;
 fn handle_neutron_collisions(
     mut events: EventReader<CollisionStarted>,
     mut commands: Commands,
-    neutrons: Query<(&CurrentAngle, &Origin), With<Neutron>>,
-    control_rods: Query<&ControlRodInsertion, With<ControlRod>>,
-    fuel_rods: Query<&FuelRod>,
+    neutrons: Query<(&CurrentAngle, &Origin, &NeutronEnergy), With<Neutron>>,
+    control_rods: Query<&ControlRodIndex, With<ControlRod>>,
+    fuel_rods: Query<&CellIndex, With<FuelRod>>,
     mut cleanup: EventWriter<Cleanup>,
+    mut reactor: ResMut<Reactor>,
+    mut rng: ResMut<SimulationRng>,
 ) {
     for CollisionStarted(entity1, entity2) in events.read() {
-        let ((neutron_angle, neutron_origin), neutron_entity, other_entity) =
+        let ((neutron_angle, neutron_origin, energy), neutron_entity, other_entity) =
             if let Ok(neutron) = neutrons.get(*entity1) {
                 (neutron, *entity1, *entity2)
             } else if let Ok(neutron) = neutrons.get(*entity2) {
@@ -43,41 +47,47 @@ fn handle_neutron_collisions(
                 continue;
             };
 
-        if let Ok(insertion) = control_rods.get(other_entity) {
-            let mut rng = rand::rng();
-            let chance = rng.random_range(0.0..1.0);
-            if chance < insertion.0 {
+        if let Ok(rod) = control_rods.get(other_entity) {
+            if reactor.control_rod_absorbs(rod.0, &mut rng) {
                 cleanup.write(Cleanup(neutron_entity));
             }
-        } else if let Ok(fuel_rod) = fuel_rods.get(other_entity) {
+        } else if let Ok(cell) = fuel_rods.get(other_entity) {
             if neutron_origin.0 == other_entity {
                 continue;
             }
 
-            commands.trigger_targets(NeutronCollision, neutron_entity);
-            match fuel_rod {
-                FuelRod::Uranium => {
-                    let angles = [-0.2 * PI, 0.0, 0.2 * PI];
-                    for angle in angles {
+            match reactor.absorb_neutron(cell.0, *energy, &mut rng) {
+                NeutronHit::Fission { prompt } => {
+                    // Prompt neutrons fan out around the direction of the one that hit.
+                    for i in 0..prompt {
+                        let offset = i as f32 - (prompt - 1) as f32 / 2.;
                         commands.trigger(LaunchNeutron {
                             origin: other_entity,
-                            angle: neutron_angle.0 + angle,
+                            angle: neutron_angle.0 + offset * 0.2 * PI,
                         });
                     }
-                    cleanup.write(Cleanup(neutron_entity));
-                }
-                FuelRod::Xenon => {
-                    commands.entity(other_entity).try_insert(FuelRod::Uranium);
-                    cleanup.write(Cleanup(neutron_entity));
                 }
+                NeutronHit::Absorbed => {}
+                NeutronHit::PassedThrough => continue,
             }
+            commands.trigger_targets(NeutronCollision, neutron_entity);
+            cleanup.write(Cleanup(neutron_entity));
         }
     }
 }
 
+/// Fast neutrons are slowed down by the water they pass through, thermal ones are absorbed by it.
 fn check_neutron_collisions_with_particles(
     mut commands: Commands,
-    neutrons: Query<(Entity, &GlobalTransform), (With<Neutron>, Without<Particle>)>,
+    mut neutrons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut NeutronEnergy,
+            Option<&ModeratedBy>,
+        ),
+        (With<Neutron>, Without<Particle>),
+    >,
     mut particles: Query<
         (
             Entity,
@@ -90,7 +100,7 @@ fn check_neutron_collisions_with_particles(
     mut cleanup: EventWriter<Cleanup>,
 ) {
     let mut hit_particles = HashSet::new();
-    for (neutron, neutron_transform) in &neutrons {
+    for (neutron, neutron_transform, mut energy, moderated_by) in &mut neutrons {
         'inner: for (particle_entity, mut particle, particle_transform, maybe_eased_motion) in
             &mut particles
         {
@@ -110,6 +120,17 @@ fn check_neutron_collisions_with_particles(
             if distance < NEUTRON_RADIUS + PARTICLE_RADIUS + COLLISION_LEEWAY
                 && !hit_particles.contains(&particle_entity)
             {
+                if *energy == NeutronEnergy::Fast {
+                    *energy = NeutronEnergy::Thermal;
+                    commands
+                        .entity(neutron)
+                        .try_insert(ModeratedBy(particle_entity));
+                    break 'inner;
+                }
+                if moderated_by.is_some_and(|moderator| moderator.0 == particle_entity) {
+                    continue;
+                }
+
                 *particle = Particle::Water(true);
                 commands.trigger_targets(BoilWaterParticle, particle_entity);
                 cleanup.write(Cleanup(neutron));
@@ -120,19 +141,31 @@ fn check_neutron_collisions_with_particles(
     }
 }
 
+fn update_neutron_colors(
+    neutrons: Query<(&NeutronEnergy, &MeshMaterial2d<ColorMaterial>), Changed<NeutronEnergy>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (energy, material) in &neutrons {
+        if let Some(material) = materials.get_mut(material) {
+            material.color = energy.color().with_alpha(material.color.alpha());
+        }
+    }
+}
+
 fn check_neutron_out_of_bounds(
     mut commands: Commands,
+    core: Single<(&ReactorCore, &GlobalTransform)>,
     query: Query<(Entity, &Neutron, &GlobalTransform), With<Neutron>>,
 ) {
+    let (core, core_transform) = core.into_inner();
+    let to_core = core_transform.affine().inverse();
     for (entity, neutron, transform) in &query {
         if *neutron == Neutron::Dying {
             continue;
         }
 
-        let w = CELL_OUTER_SIZE * (CELL_COLUMNS as f32);
-        let h = CELL_OUTER_SIZE * (CELL_ROWS as f32);
-        let rect = Rect::from_corners(Vec2::new(-w / 2., -h / 2.), Vec2::new(w / 2., h / 2.));
-        if !rect.contains(transform.translation().xy()) {
+        let point = to_core.transform_point3(transform.translation()).xy();
+        if !core.contains(point) {
             commands.entity(entity).try_insert((
                 Neutron::Dying,
                 Expiry(Timer::from_seconds(0.5, TimerMode::Once)),
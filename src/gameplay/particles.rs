@@ -5,6 +5,7 @@ use super::*;
 pub fn plugin(app: &mut App) {
     app.register_type::<WaterContainer>();
     app.register_type::<SteamContainer>();
+    app.register_type::<TurbineContainer>();
     app.register_type::<ParticleContainer>();
     app.register_type::<ContainerSize>();
     app.register_type::<EasedMotion>();
@@ -14,9 +15,11 @@ pub fn plugin(app: &mut App) {
         (update_eased_movement, update_cell_colors).run_if(in_state(Screen::Gameplay)),
     );
     app.add_observer(handle_move_particle)
-        .add_observer(handle_create_water_particles)
         .add_observer(handle_flow_water_particles_into_cell)
+        .add_observer(handle_pump_water_particles_into_cell)
+        .add_observer(handle_vent_steam_particles_from_cell)
         .add_observer(handle_boil_water_particle)
+        .add_observer(handle_boil_water_particles_in_cell)
         .add_observer(handle_finished_particle_motion);
 }
 
@@ -25,7 +28,7 @@ pub fn plugin(app: &mut App) {
 #[reflect(Component)]
 pub struct WaterContainer;
 
-#[derive(Component, Copy, Clone, Reflect, Debug)]
+#[derive(Component, Copy, Clone, Default, Reflect, Debug)]
 #[reflect(Component)]
 pub struct WaterFlow(usize);
 
@@ -34,22 +37,8 @@ impl WaterFlow {
         self.0
     }
 
-    pub fn decrease(&mut self) {
-        if self.0 > 1 {
-            self.0 -= 1;
-        }
-    }
-
-    pub fn increase(&mut self) {
-        if self.0 < 50 {
-            self.0 += 1;
-        }
-    }
-}
-
-impl Default for WaterFlow {
-    fn default() -> Self {
-        Self(3)
+    pub fn set(&mut self, flow: usize) {
+        self.0 = flow;
     }
 }
 
@@ -57,6 +46,12 @@ impl Default for WaterFlow {
 #[reflect(Component)]
 pub struct SteamContainer;
 
+/// Sits between the steam and the energy container, which gets its particles from here.
+#[derive(Component, Copy, Clone, Reflect, Debug)]
+#[reflect(Component)]
+#[require(Turbine)]
+pub struct TurbineContainer;
+
 #[derive(Component, Copy, Clone, Reflect, Debug)]
 #[reflect(Component)]
 #[require(PowerDemand, NextPowerDemand)]
@@ -93,13 +88,18 @@ pub struct MoveParticle {
 }
 
 #[derive(Event, Clone, Reflect, Debug)]
-pub struct CreateWaterParticles(pub usize);
+pub struct FlowWaterParticlesIntoCell;
+
+/// Shows water the reactor model has already pumped into a cell flowing in from the container.
+#[derive(Event, Clone, Reflect, Debug)]
+pub struct PumpWaterParticlesIntoCell(pub usize);
 
 #[derive(Event, Clone, Reflect, Debug)]
-pub struct FlowWaterParticlesIntoCell;
+pub struct VentSteamParticlesFromCell(pub usize);
 
+/// Shows water the reactor model has already boiled in a cell turning into steam.
 #[derive(Event, Clone, Reflect, Debug)]
-pub struct VentSteamParticleFromCell;
+pub struct BoilWaterParticlesInCell(pub usize);
 
 fn handle_move_particle(
     trigger: Trigger<MoveParticle>,
@@ -123,31 +123,55 @@ fn handle_move_particle(
     Ok(())
 }
 
-fn handle_create_water_particles(
-    trigger: Trigger<CreateWaterParticles>,
-    mut container: Single<&mut ParticleContainer, With<WaterContainer>>,
-) {
-    container.count = (container.count + trigger.event().0).clamp(0, MAX_WATER_STORED);
-}
-
 fn handle_flow_water_particles_into_cell(
     trigger: Trigger<FlowWaterParticlesIntoCell>,
     mut commands: Commands,
-    container: Single<(&mut ParticleContainer, &GlobalTransform, &WaterFlow), With<WaterContainer>>,
-    mut cells: Query<(Entity, &GlobalTransform, &mut ParticleCount)>,
+    mut reactor: ResMut<Reactor>,
+    container: Single<&GlobalTransform, With<WaterContainer>>,
+    cells: Query<(&CellIndex, &GlobalTransform), With<Cell>>,
 ) -> Result {
-    let (mut container, container_transform, water_flow) = container.into_inner();
-    let (cell, cell_transform, mut particle_count) = cells.get_mut(trigger.target())?;
+    let (index, cell_transform) = cells.get(trigger.target())?;
+    let count = reactor.flow_water_into_cell(index.0);
+    spawn_water_from_container(
+        &mut commands,
+        trigger.target(),
+        cell_transform,
+        *container,
+        count,
+    );
+    Ok(())
+}
 
+fn handle_pump_water_particles_into_cell(
+    trigger: Trigger<PumpWaterParticlesIntoCell>,
+    mut commands: Commands,
+    container: Single<&GlobalTransform, With<WaterContainer>>,
+    cells: Query<&GlobalTransform, With<Cell>>,
+) -> Result {
+    let cell_transform = cells.get(trigger.target())?;
+    spawn_water_from_container(
+        &mut commands,
+        trigger.target(),
+        cell_transform,
+        *container,
+        trigger.event().0,
+    );
+    Ok(())
+}
+
+/// Spawns water particles in a cell where the water container is, from where they move in.
+fn spawn_water_from_container(
+    commands: &mut Commands,
+    cell: Entity,
+    cell_transform: &GlobalTransform,
+    container_transform: &GlobalTransform,
+    count: usize,
+) {
     let particle_transform = cell_transform.affine().inverse() * container_transform.affine();
     let mut particle_transform = Transform::from_matrix(particle_transform.into());
     particle_transform.translation.z = 20.0;
 
-    let count = container.count.min(water_flow.0);
     for _ in 0..count {
-        container.count -= 1;
-        particle_count.increment(1);
-
         commands.spawn((
             Name::new("Water particle"),
             Particle::Water(false),
@@ -156,6 +180,46 @@ fn handle_flow_water_particles_into_cell(
             TargetAngle::default(),
         ));
     }
+}
+
+fn handle_vent_steam_particles_from_cell(
+    trigger: Trigger<VentSteamParticlesFromCell>,
+    mut commands: Commands,
+    cells: Query<&Children, With<Cell>>,
+    steam_particles: Query<(Entity, &GlobalTransform, &Lifetime), With<Particle>>,
+    container: Single<(Entity, &GlobalTransform), With<SteamContainer>>,
+) -> Result {
+    let (container_entity, container_transform) = container.into_inner();
+    let children = cells.get(trigger.target())?;
+
+    let mut particles: Vec<_> = children
+        .iter()
+        .flat_map(|entity| steam_particles.get(entity))
+        .collect();
+    particles.sort_by(|(_, _, a), (_, _, b)| {
+        b.0.elapsed()
+            .partial_cmp(&a.0.elapsed())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let vented_particles = particles.into_iter().take(trigger.event().0);
+    for (entity, particle_transform, _) in vented_particles {
+        let new_transform = particle_transform.reparented_to(container_transform);
+        commands
+            .entity(entity)
+            .try_remove::<Lifetime>()
+            .try_remove::<TargetAngle>()
+            .try_remove::<EasedMotion>()
+            .try_remove::<InCell>()
+            .try_insert((ChildOf(container_entity), new_transform));
+        commands.trigger_targets(
+            MoveParticle {
+                to: Vec2::new(0.0, 0.0),
+                stop_current: false,
+            },
+            entity,
+        );
+    }
 
     Ok(())
 }
@@ -186,17 +250,65 @@ fn handle_boil_water_particle(
     trigger: Trigger<BoilWaterParticle>,
     mut cleanup: EventWriter<Cleanup>,
     mut commands: Commands,
+    mut reactor: ResMut<Reactor>,
     query: Query<&ChildOf, With<Particle>>,
-    mut particle_counts: Query<&mut ParticleCount>,
+    cells: Query<&CellIndex, With<Cell>>,
 ) -> Result {
     let child_of = query.get(trigger.target())?;
-    let mut particle_count = particle_counts.get_mut(child_of.0)?;
+    let index = cells.get(child_of.0)?;
+
+    if reactor.boil_water(index.0) {
+        spawn_steam_from_water(
+            &mut commands,
+            trigger.target(),
+            reactor.balance.steam_generated_per_water,
+        );
+    }
+
+    cleanup.write(Cleanup(trigger.target()));
+
+    Ok(())
+}
 
-    for _ in 0..STEAM_GENERATED_PER_WATER {
-        particle_count.increment(1);
+fn handle_boil_water_particles_in_cell(
+    trigger: Trigger<BoilWaterParticlesInCell>,
+    mut commands: Commands,
+    mut cleanup: EventWriter<Cleanup>,
+    reactor: Res<Reactor>,
+    cells: Query<&Children, With<Cell>>,
+    mut water_particles: Query<&mut Particle, With<InCell>>,
+) -> Result {
+    let children = cells.get(trigger.target())?;
 
+    let mut boiled = 0;
+    for &entity in children {
+        if boiled == trigger.event().0 {
+            break;
+        }
+        let Ok(mut particle) = water_particles.get_mut(entity) else {
+            continue;
+        };
+        if *particle != Particle::Water(false) {
+            continue;
+        }
+
+        *particle = Particle::Water(true);
+        spawn_steam_from_water(
+            &mut commands,
+            entity,
+            reactor.balance.steam_generated_per_water,
+        );
+        cleanup.write(Cleanup(entity));
+        boiled += 1;
+    }
+
+    Ok(())
+}
+
+fn spawn_steam_from_water(commands: &mut Commands, water: Entity, count: usize) {
+    for _ in 0..count {
         commands
-            .entity(trigger.target())
+            .entity(water)
             .clone_and_spawn_with(|config| {
                 config.deny::<Particle>().deny::<Name>();
             })
@@ -206,25 +318,12 @@ fn handle_boil_water_particle(
                 Lifetime(Stopwatch::new()),
             ));
     }
-
-    cleanup.write(Cleanup(trigger.target()));
-    particle_count.decrement(1);
-
-    Ok(())
 }
 
 fn handle_finished_particle_motion(
     trigger: Trigger<FinishedEasedMotion>,
     mut commands: Commands,
     particles: Query<(&Particle, Option<&InCell>)>,
-    steam_container: Single<
-        &mut ParticleContainer,
-        (With<SteamContainer>, Without<EnergyContainer>),
-    >,
-    energy_container: Single<
-        &mut ParticleContainer,
-        (With<EnergyContainer>, Without<SteamContainer>),
-    >,
     mut cleanup: EventWriter<Cleanup>,
 ) {
     if let Ok((&particle, maybe_in_cell)) = particles.get(trigger.target()) {
@@ -233,12 +332,9 @@ fn handle_finished_particle_motion(
                 Particle::Water(_) => {
                     commands.entity(trigger.target()).try_insert(InCell);
                 }
-                Particle::Steam => {
-                    steam_container.into_inner().count += 1;
-                    cleanup.write(Cleanup(trigger.target()));
-                }
-                Particle::Energy => {
-                    energy_container.into_inner().count += 1;
+                // Steam and energy particles only show the transfer, the reactor model has
+                // already counted them in their containers.
+                Particle::Steam | Particle::Energy => {
                     cleanup.write(Cleanup(trigger.target()));
                 }
             }
@@ -1,91 +1,34 @@
 use bevy::prelude::*;
 
-use crate::screens::game_over::{GameOver, GameOverCause};
-
 use super::{ui::ParticleContainerColor, *};
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(
-        RunSimulation,
-        (turn_steam_into_power, handle_lack_of_power).in_set(PhaseSystems::PowerGeneration),
-    );
     app.add_systems(
         Update,
-        (
-            tick_timers,
-            increase_power_demand,
-            increase_power_demand_increase_rate,
-            handle_lack_of_power_timer,
-        )
+        (handle_lack_of_power, handle_lack_of_power_timer)
             .chain()
             .run_if(in_state(Screen::Gameplay))
             .in_set(PausableSystems),
     );
+    app.add_observer(handle_generate_energy_particles);
 }
 
-#[derive(Component, Clone, Default, Reflect)]
-pub struct TicksWithoutPower(pub usize);
-
-fn tick_timers(time: Res<Time>, mut query: Single<&mut NextPowerDemand>) {
-    query.tutorial_timer.tick(time.delta());
-    if query.tutorial_timer.finished() {
-        query.demand_timer.tick(time.delta());
-        query.demand_rate_timer.tick(time.delta());
-    }
-}
+#[derive(Event, Clone, Reflect, Debug)]
+pub struct GenerateEnergyParticles(pub usize);
 
-fn increase_power_demand(query: Single<(&NextPowerDemand, &mut PowerDemand)>) {
-    let (next, mut current) = query.into_inner();
-
-    if next.demand_timer.just_finished() {
-        current.0 += next.delta;
-    }
-}
-
-fn increase_power_demand_increase_rate(mut next: Single<&mut NextPowerDemand>) {
-    if next.demand_rate_timer.just_finished() {
-        next.delta *= 2;
-    }
-}
-
-fn turn_steam_into_power(
+fn handle_generate_energy_particles(
+    trigger: Trigger<GenerateEnergyParticles>,
     mut commands: Commands,
-    energy_container: Single<
-        (
-            Entity,
-            &ParticleContainer,
-            &GlobalTransform,
-            &PowerDemand,
-            &NextPowerDemand,
-            &mut TicksWithoutPower,
-        ),
-        (With<EnergyContainer>, Without<SteamContainer>),
-    >,
-    steam_container: Single<
-        (&mut ParticleContainer, &GlobalTransform),
-        (With<SteamContainer>, Without<EnergyContainer>),
-    >,
+    energy_container: Single<(Entity, &GlobalTransform), With<EnergyContainer>>,
+    turbine_container: Single<&GlobalTransform, With<TurbineContainer>>,
 ) {
-    let (
-        energy_entity,
-        energy_container,
-        energy_transform,
-        demand,
-        next_demand,
-        mut ticks_without_power,
-    ) = energy_container.into_inner();
-    let (mut steam_container, steam_transform) = steam_container.into_inner();
-
-    let diff = demand.0.saturating_sub(energy_container.count);
-    let count = steam_container.count.min(diff + next_demand.delta);
+    let (energy_entity, energy_transform) = energy_container.into_inner();
 
-    for _ in 0..count {
-        steam_container.count -= 1;
-
-        let particle_transform = energy_transform.affine().inverse() * steam_transform.affine();
-        let mut particle_transform = Transform::from_matrix(particle_transform.into());
-        particle_transform.translation.z = 20.0;
+    let particle_transform = energy_transform.affine().inverse() * turbine_container.affine();
+    let mut particle_transform = Transform::from_matrix(particle_transform.into());
+    particle_transform.translation.z = 20.0;
 
+    for _ in 0..trigger.event().0 {
         commands.spawn((
             Name::new("Energy particle"),
             Particle::Energy,
@@ -99,33 +42,27 @@ fn turn_steam_into_power(
             },
         ));
     }
-
-    if diff > count {
-        ticks_without_power.0 += 1;
-    } else {
-        ticks_without_power.0 = 0;
-    }
 }
 
 fn handle_lack_of_power(
     mut commands: Commands,
-    query: Single<(Entity, &TicksWithoutPower)>,
-    energy_container: Single<&ParticleContainer, With<EnergyContainer>>,
+    reactor: Res<Reactor>,
+    query: Single<(Entity, Has<LackOfPowerTimer>), With<EnergyContainer>>,
 ) {
-    let (entity, ticks) = query.into_inner();
-    if ticks.0 > 20 {
-        commands.trigger(GameOver {
-            cause: GameOverCause::NotEnoughPower,
-            power_generated: energy_container.count,
-        });
-    } else if ticks.0 > 5 {
-        commands
-            .entity(entity)
-            .try_insert(LackOfPowerTimer(Timer::from_seconds(1.0, TimerMode::Once)));
-    } else {
+    let (entity, warning) = query.into_inner();
+    if reactor.ticks_without_power > reactor.balance.power_warn_ticks {
+        if !warning {
+            commands
+                .entity(entity)
+                .try_insert(LackOfPowerTimer(Timer::from_seconds(
+                    1.0,
+                    TimerMode::Repeating,
+                )));
+        }
+    } else if warning {
         commands
             .entity(entity)
-            .remove::<LackOfPowerTimer>()
+            .try_remove::<LackOfPowerTimer>()
             .try_insert(ParticleContainerColor(URANIUM_COLOR));
     }
 }
@@ -0,0 +1,114 @@
+//! Coolant pumps, see [`PumpState`].
+
+use bevy::prelude::*;
+
+use super::*;
+
+const PUMP_TRIPPED_COLOR: Color = Color::srgb(1.0, 0.35, 0.35);
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Pump>();
+
+    app.add_observer(spawn_pump_indicator)
+        .add_observer(setup_cell_button_on_add);
+    app.add_systems(
+        Update,
+        (sync_pumps, update_pump_indicators)
+            .chain()
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<Reactor>)),
+    );
+}
+
+/// The pump of a cell as the reactor model has it.
+#[derive(Component, Clone, Copy, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Pump {
+    pub rate: usize,
+    pub tripped: bool,
+}
+
+#[derive(Component)]
+struct PumpIndicator;
+
+fn spawn_pump_indicator(trigger: Trigger<OnAdd, Cell>, mut commands: Commands) {
+    commands.spawn((
+        Name::new("Pump indicator"),
+        ChildOf(trigger.target()),
+        PumpIndicator,
+        Text2d::default(),
+        TextFont::from_font_size(14.),
+        TextColor(WATER_COLOR.into()),
+        UseBoldFont,
+        Transform::from_xyz(0., -CELL_RADIUS * 0.7, 30.),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+fn setup_cell_button_on_add(trigger: Trigger<OnAdd, CellButton>, mut commands: Commands) {
+    commands.entity(trigger.target()).observe(on_scroll_cell);
+}
+
+/// Turns the pump of a cell up or down.
+fn on_scroll_cell(
+    trigger: Trigger<Pointer<Scroll>>,
+    mut commands: Commands,
+    buttons: Query<&CellButton>,
+    cells: Query<(&CellIndex, &Pump), With<Cell>>,
+) {
+    let scroll = trigger.event().y;
+    if scroll == 0. {
+        return;
+    }
+    let Ok(button) = buttons.get(trigger.target()) else {
+        return;
+    };
+    let Ok((index, pump)) = cells.get(button.0) else {
+        return;
+    };
+    let rate = if scroll > 0. {
+        pump.rate + 1
+    } else {
+        pump.rate.saturating_sub(1)
+    };
+    commands.trigger(PlayerAction::SetPumpRate {
+        cell: index.0,
+        rate,
+    });
+}
+
+fn sync_pumps(reactor: Res<Reactor>, mut pumps: Query<(&CellIndex, &mut Pump)>) {
+    for (index, mut pump) in &mut pumps {
+        if let Some(cell) = reactor.cells.get(index.0) {
+            pump.set_if_neq(Pump {
+                rate: cell.pump.rate,
+                tripped: cell.pump.tripped > 0,
+            });
+        }
+    }
+}
+
+/// Shows the rate of every pump that's switched on, in red while it's tripped.
+fn update_pump_indicators(
+    cells: Query<(&Pump, &Children), Changed<Pump>>,
+    mut indicators: Query<(&mut Text2d, &mut TextColor, &mut Visibility), With<PumpIndicator>>,
+) {
+    for (pump, children) in &cells {
+        for &entity in children {
+            let Ok((mut text, mut color, mut visibility)) = indicators.get_mut(entity) else {
+                continue;
+            };
+            text.0 = format!("pump {}", pump.rate);
+            color.0 = if pump.tripped {
+                PUMP_TRIPPED_COLOR
+            } else {
+                WATER_COLOR.into()
+            };
+            *visibility = if pump.rate > 0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Recording and replaying player input.
+//!
+//! Every player action goes through [`PlayerAction`] and is stamped with the number of
+//! [`RunSimulation`](super::schedule::RunSimulation) ticks that had run when it happened. Together
+//! with the seed of the run and the length of every frame, which neutrons fly by, that is enough
+//! to play the run back, see [`ReplayFile`].
+
+use std::{collections::VecDeque, path::Path, time::Duration};
+
+use bevy::{app::RunFixedMainLoopSystem, prelude::*};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::PausableSystems;
+
+/// Environment variable that can be used to start the first run as a replay of a file.
+const REPLAY_ENV_VAR: &str = "REACTOR_REPLAY";
+/// Where the input of the last finished run is written to.
+#[cfg(not(target_family = "wasm"))]
+const LAST_REPLAY_PATH: &str = "replays/last.ron";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Recording>();
+    app.insert_resource(NextReplay(replay_from_env()));
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (
+            start_replay.before(seed_simulation_rng),
+            start_recording
+                .after(seed_simulation_rng)
+                .before(create_reactor),
+        ),
+    );
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(OnExit(Screen::Gameplay), save_recording);
+    app.add_systems(OnExit(Screen::Gameplay), stop_replay);
+
+    app.add_systems(
+        Update,
+        feed_replay
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<ActiveReplay>))
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        RunFixedMainLoop,
+        play_back_frame
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<ActiveReplay>))
+            .in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+    );
+    app.add_systems(
+        Last,
+        record_frame.run_if(in_state(Screen::Gameplay).and(not(resource_exists::<ActiveReplay>))),
+    );
+
+    app.add_observer(on_player_action)
+        .add_observer(apply_player_action);
+}
+
+/// Something the player did that affects the simulation.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerAction {
+    ToggleControlRod {
+        rod: usize,
+    },
+    /// Moves a control rod to `percent` insertion.
+    SetControlRodTarget {
+        rod: usize,
+        percent: u8,
+    },
+    AddWater {
+        cell: usize,
+    },
+    DistributeWater,
+    IncreaseWaterFlow,
+    DecreaseWaterFlow,
+    Refuel {
+        cell: usize,
+    },
+    Scram,
+    ResetScram,
+    /// Sets how much water the pump of a cell moves per tick.
+    SetPumpRate {
+        cell: usize,
+        rate: usize,
+    },
+    /// Opens the throttle valve of the turbine to `percent`.
+    SetThrottle {
+        percent: u8,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedAction {
+    /// Number of simulation ticks that had run when the action happened.
+    pub tick: u64,
+    pub action: PlayerAction,
+}
+
+/// Everything needed to reproduce a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub seed: u64,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub layout: ReactorLayout,
+    pub actions: Vec<RecordedAction>,
+    /// Virtual time in nanoseconds that passed in every frame that moved the run, or `0` for a
+    /// tick stepped while paused. Replays without frames run on the time that really passes.
+    #[serde(default)]
+    pub frames: Vec<u64>,
+}
+
+impl ReplayFile {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// The input of the current run, or of the last one once it has ended.
+#[derive(Resource, Clone, Default, Debug, Deref)]
+pub struct Recording(pub ReplayFile);
+
+/// A replay to play back in the next run instead of taking player input.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct NextReplay(pub Option<ReplayFile>);
+
+/// The replay being played back in the current run. Player input is ignored while it exists.
+#[derive(Resource, Debug)]
+pub struct ActiveReplay {
+    pending: VecDeque<RecordedAction>,
+    frames: VecDeque<u64>,
+}
+
+/// Carries out a [`PlayerAction`] without recording it.
+#[derive(Event, Clone, Copy, Debug)]
+struct ApplyPlayerAction(PlayerAction);
+
+fn start_replay(
+    mut commands: Commands,
+    mut next_replay: ResMut<NextReplay>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut difficulty: ResMut<Difficulty>,
+    mut layout: ResMut<ReactorLayout>,
+) {
+    let Some(replay) = next_replay.0.take() else {
+        return;
+    };
+    info!("replaying {} actions", replay.actions.len());
+    next_seed.0 = Some(replay.seed);
+    *difficulty = replay.difficulty;
+    *layout = replay.layout.clone();
+    commands.insert_resource(ActiveReplay {
+        pending: replay.actions.into(),
+        frames: replay.frames.into(),
+    });
+}
+
+fn start_recording(
+    mut recording: ResMut<Recording>,
+    rng: Res<SimulationRng>,
+    difficulty: Res<Difficulty>,
+    layout: Res<ReactorLayout>,
+    replay: Option<Res<ActiveReplay>>,
+) {
+    // A replay records itself, so it can be watched again or saved.
+    let (actions, frames) = replay
+        .map(|replay| {
+            (
+                replay.pending.iter().copied().collect(),
+                replay.frames.iter().copied().collect(),
+            )
+        })
+        .unwrap_or_default();
+    recording.0 = ReplayFile {
+        seed: rng.seed(),
+        difficulty: *difficulty,
+        layout: layout.clone(),
+        actions,
+        frames,
+    };
+}
+
+fn stop_replay(mut commands: Commands) {
+    commands.remove_resource::<ActiveReplay>();
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_recording(recording: Res<Recording>) {
+    if let Err(err) = recording.save(LAST_REPLAY_PATH) {
+        warn!("failed to save replay to {LAST_REPLAY_PATH}: {err}");
+    }
+}
+
+fn feed_replay(
+    mut commands: Commands,
+    time: Res<Time<Simulation>>,
+    mut replay: ResMut<ActiveReplay>,
+) {
+    let ticks = time.context().ticks();
+    while let Some(recorded) = replay.pending.front() {
+        if recorded.tick > ticks {
+            break;
+        }
+        commands.trigger(ApplyPlayerAction(recorded.action));
+        replay.pending.pop_front();
+    }
+}
+
+/// Records the frame for [`ReplayFile::frames`], right before the simulation clock takes it.
+fn record_frame(
+    time: Res<Time<Virtual>>,
+    simulation: Res<Time<Simulation>>,
+    mut recording: ResMut<Recording>,
+) {
+    let delta = time.delta();
+    if !delta.is_zero() {
+        recording.0.frames.push(delta.as_nanos() as u64);
+    }
+    for _ in 0..simulation.context().requested_steps() {
+        recording.0.frames.push(0);
+    }
+}
+
+/// Moves the clocks by the next recorded frame instead of the time that really passed, so the
+/// neutrons fly and collide the way they did.
+fn play_back_frame(
+    mut replay: ResMut<ActiveReplay>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+    mut simulation: ResMut<Time<Simulation>>,
+) {
+    if virtual_time.is_paused() {
+        return;
+    }
+    let Some(frame) = replay.frames.pop_front() else {
+        return;
+    };
+    if frame == 0 {
+        simulation.context_mut().request_step();
+    }
+    virtual_time.advance_by(Duration::from_nanos(frame));
+    *time = virtual_time.as_generic();
+}
+
+fn on_player_action(
+    trigger: Trigger<PlayerAction>,
+    mut commands: Commands,
+    time: Res<Time<Simulation>>,
+    mut recording: ResMut<Recording>,
+    replay: Option<Res<ActiveReplay>>,
+) {
+    if replay.is_some() {
+        return;
+    }
+
+    let action = *trigger.event();
+    recording.0.actions.push(RecordedAction {
+        tick: time.context().ticks(),
+        action,
+    });
+    commands.trigger(ApplyPlayerAction(action));
+}
+
+fn apply_player_action(
+    trigger: Trigger<ApplyPlayerAction>,
+    mut commands: Commands,
+    mut reactor: ResMut<Reactor>,
+    mut rng: ResMut<SimulationRng>,
+    cells: Query<(Entity, &CellIndex), With<Cell>>,
+) {
+    match trigger.0 {
+        PlayerAction::ToggleControlRod { rod } => reactor.toggle_control_rod(rod),
+        PlayerAction::SetControlRodTarget { rod, percent } => {
+            reactor.set_control_rod_target(rod, percent as f32 / 100.)
+        }
+        PlayerAction::AddWater { cell } => {
+            if let Some((entity, _)) = cells.iter().find(|(_, index)| index.0 == cell) {
+                commands.trigger_targets(FlowWaterParticlesIntoCell, entity);
+            }
+        }
+        PlayerAction::DistributeWater => {
+            let mut cells: Vec<_> = cells.iter().collect();
+            cells.sort_by_key(|(_, index)| index.0);
+            cells.shuffle(rng.stream(RngStream::Water));
+            for (entity, _) in cells {
+                commands.trigger_targets(FlowWaterParticlesIntoCell, entity);
+            }
+        }
+        PlayerAction::IncreaseWaterFlow => reactor.increase_water_flow(),
+        PlayerAction::DecreaseWaterFlow => reactor.decrease_water_flow(),
+        PlayerAction::Refuel { cell } => {
+            reactor.refuel(cell);
+        }
+        PlayerAction::Scram => reactor.scram(),
+        PlayerAction::ResetScram => {
+            reactor.reset_scram();
+        }
+        PlayerAction::SetPumpRate { cell, rate } => reactor.set_pump_rate(cell, rate),
+        PlayerAction::SetThrottle { percent } => reactor.set_throttle(percent as f32 / 100.),
+    }
+}
+
+fn replay_from_env() -> Option<ReplayFile> {
+    let path = std::env::var(REPLAY_ENV_VAR).ok()?;
+    match ReplayFile::load(&path) {
+        Ok(replay) => Some(replay),
+        Err(err) => {
+            warn!("ignoring replay {path:?} from {REPLAY_ENV_VAR}: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_file_survives_saving_and_loading() {
+        let replay = ReplayFile {
+            seed: 42,
+            difficulty: Difficulty::Hard,
+            layout: ReactorLayout::hexagon(2),
+            actions: vec![
+                RecordedAction {
+                    tick: 3,
+                    action: PlayerAction::Scram,
+                },
+                RecordedAction {
+                    tick: 9,
+                    action: PlayerAction::SetThrottle { percent: 40 },
+                },
+            ],
+            frames: vec![16_666_667, 0, 33_333_333],
+        };
+        let path = std::env::temp_dir().join(format!("reactor-replay-{}.ron", std::process::id()));
+        replay.save(&path).unwrap();
+        let loaded = ReplayFile::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), replay);
+    }
+}
@@ -0,0 +1,116 @@
+//! Seeded random number generation for the simulation.
+//!
+//! Every random decision made by gameplay code draws from [`SimulationRng`], so a run can be
+//! reproduced exactly by starting it again with the same seed, input and frame times, which is
+//! what a [`ReplayFile`](super::ReplayFile) records.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable that can be used to pin the seed of the first run.
+const SEED_ENV_VAR: &str = "REACTOR_SEED";
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<NextRunSeed>();
+    app.insert_resource(NextRunSeed(seed_from_env()));
+    app.insert_resource(SimulationRng::new(rand::rng().random()));
+}
+
+/// The seed to use for the next run. A random seed is picked when this is `None`.
+#[derive(Resource, Clone, Copy, Default, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct NextRunSeed(pub Option<u64>);
+
+/// Independent sub-streams of [`SimulationRng`].
+///
+/// Each system draws from its own stream so that adding or removing random calls in one system
+/// doesn't shift the numbers seen by the others.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Reflect, Serialize, Deserialize)]
+pub enum RngStream {
+    Fuel,
+    NeutronRelease,
+    Xenon,
+    ControlRods,
+    Water,
+    Fission,
+    Precursors,
+    Controller,
+}
+
+#[derive(Resource)]
+pub struct SimulationRng {
+    seed: u64,
+    streams: HashMap<RngStream, ChaCha8Rng>,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Captures how far each stream has advanced, so the generator can be restored later.
+    pub fn snapshot(&self) -> RngSnapshot {
+        RngSnapshot {
+            seed: self.seed,
+            // Word positions only exceed a `u64` after ~10^20 draws.
+            positions: self
+                .streams
+                .iter()
+                .map(|(&stream, rng)| (stream, rng.get_word_pos() as u64))
+                .collect(),
+        }
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream as u64);
+            rng
+        })
+    }
+}
+
+/// The state of a [`SimulationRng`] in a form that can be saved.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RngSnapshot {
+    pub seed: u64,
+    pub positions: Vec<(RngStream, u64)>,
+}
+
+impl RngSnapshot {
+    pub fn restore(&self) -> SimulationRng {
+        let mut rng = SimulationRng::new(self.seed);
+        for &(stream, position) in &self.positions {
+            rng.stream(stream).set_word_pos(position.into());
+        }
+        rng
+    }
+}
+
+/// Reseeds [`SimulationRng`] at the start of a run, consuming [`NextRunSeed`].
+pub fn seed_simulation_rng(mut next_seed: ResMut<NextRunSeed>, mut rng: ResMut<SimulationRng>) {
+    let seed = next_seed.0.take().unwrap_or_else(|| rand::rng().random());
+    info!("starting run with seed {seed}");
+    *rng = SimulationRng::new(seed);
+}
+
+fn seed_from_env() -> Option<u64> {
+    let value = std::env::var(SEED_ENV_VAR).ok()?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(err) => {
+            warn!("ignoring invalid {SEED_ENV_VAR} value {value:?}: {err}");
+            None
+        }
+    }
+}
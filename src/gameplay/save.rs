@@ -0,0 +1,254 @@
+//! Saving a run in progress and resuming it later.
+//!
+//! Most of a run lives in [`ReactorState`], so a save is that model plus the random number
+//! generator, the simulation clock, the recorded input and the neutrons that are in flight.
+
+use std::{path::Path, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Where the run in progress is saved to.
+const SAVE_PATH: &str = "saves/run.ron";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ResumeRun>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (
+            resume_run.after(create_reactor).before(spawn_reactor),
+            restore_neutrons.after(spawn_reactor),
+        ),
+    );
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        app.add_observer(save_run);
+        app.add_observer(delete_saved_run);
+        app.add_systems(Last, save_on_exit.run_if(in_state(Screen::Gameplay)));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedNeutron {
+    /// Index of the cell whose fuel rod released the neutron.
+    pub cell: usize,
+    pub angle: f32,
+    pub distance: f32,
+    pub dying: bool,
+    #[serde(default)]
+    pub energy: NeutronEnergy,
+    pub expiry: Duration,
+    pub expiry_elapsed: Duration,
+}
+
+/// A snapshot of everything needed to continue a run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedRun {
+    pub reactor: ReactorState,
+    pub rng: RngSnapshot,
+    pub ticks: u64,
+    pub overstep: Duration,
+    pub recording: ReplayFile,
+    pub neutrons: Vec<SavedNeutron>,
+}
+
+impl SavedRun {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// A saved run to continue when gameplay starts, instead of starting a fresh one.
+#[derive(Resource, Default)]
+pub struct ResumeRun(pub Option<SavedRun>);
+
+/// Writes the run in progress to disk.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaveRun;
+
+/// Whether there is a saved run that can be continued.
+pub fn has_saved_run() -> bool {
+    !cfg!(target_family = "wasm") && Path::new(SAVE_PATH).exists()
+}
+
+/// Loads the saved run so that the next gameplay screen continues it.
+pub fn load_saved_run(resume: &mut ResumeRun) -> anyhow::Result<()> {
+    resume.0 = Some(SavedRun::load(SAVE_PATH)?);
+    Ok(())
+}
+
+/// The neutrons that were in flight when the run was saved, spawned once the core exists.
+#[derive(Resource)]
+struct PendingNeutrons(Vec<SavedNeutron>);
+
+fn resume_run(
+    mut commands: Commands,
+    mut resume: ResMut<ResumeRun>,
+    mut rng: ResMut<SimulationRng>,
+    mut time: ResMut<Time<Simulation>>,
+    mut recording: ResMut<Recording>,
+    mut next_phase: ResMut<NextState<Phase>>,
+) {
+    let Some(saved) = resume.0.take() else {
+        return;
+    };
+
+    let layout = &saved.reactor.layout;
+    let cells_match = saved
+        .reactor
+        .cells
+        .iter()
+        .map(|cell| cell.position)
+        .eq(layout.iter_cell_positions());
+    let control_rods_match = saved
+        .reactor
+        .control_rods
+        .iter()
+        .map(|rod| rod.position)
+        .eq(layout.iter_control_positions());
+    if !cells_match || !control_rods_match {
+        warn!("saved run doesn't match its reactor layout, starting a new run instead");
+        return;
+    }
+
+    info!(
+        "resuming run with seed {} at tick {}",
+        saved.rng.seed, saved.ticks
+    );
+    *rng = saved.rng.restore();
+    time.context_mut().resume(saved.ticks, saved.overstep);
+    recording.0 = saved.recording;
+    next_phase.set(saved.reactor.phase);
+    commands.insert_resource(Reactor(saved.reactor));
+    commands.insert_resource(PendingNeutrons(saved.neutrons));
+}
+
+fn restore_neutrons(
+    mut commands: Commands,
+    pending: Option<Res<PendingNeutrons>>,
+    reactor: Res<Reactor>,
+    fuel_rods: Query<(Entity, &CellIndex), With<FuelRod>>,
+) {
+    let Some(pending) = pending else {
+        return;
+    };
+    commands.remove_resource::<PendingNeutrons>();
+
+    for saved in &pending.0 {
+        let Some((origin, _)) = fuel_rods.iter().find(|(_, index)| index.0 == saved.cell) else {
+            continue;
+        };
+        let Some(cell) = reactor.cells.get(saved.cell) else {
+            continue;
+        };
+
+        let origin_translation = reactor.layout.translation(cell.position);
+        let translation = origin_translation + Vec2::from_angle(saved.angle) * saved.distance;
+        let mut expiry = Timer::new(saved.expiry, TimerMode::Once);
+        expiry.set_elapsed(saved.expiry_elapsed);
+        commands
+            .spawn(neutron(
+                origin,
+                saved.angle,
+                translation,
+                saved.expiry.as_secs_f32(),
+            ))
+            .insert((
+                if saved.dying {
+                    Neutron::Dying
+                } else {
+                    Neutron::Active
+                },
+                CurrentDistance(saved.distance),
+                saved.energy,
+                Expiry(expiry),
+            ));
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_run(
+    _: Trigger<SaveRun>,
+    reactor: Res<Reactor>,
+    rng: Res<SimulationRng>,
+    time: Res<Time<Simulation>>,
+    recording: Res<Recording>,
+    replay: Option<Res<ActiveReplay>>,
+    neutrons: Query<(
+        &Neutron,
+        &NeutronEnergy,
+        &Origin,
+        &CurrentAngle,
+        &CurrentDistance,
+        &Expiry,
+    )>,
+    fuel_rods: Query<&CellIndex, With<FuelRod>>,
+) {
+    // A replay can be watched again from its file, there's nothing to continue.
+    if replay.is_some() || reactor.failure.is_some() {
+        return;
+    }
+
+    let saved = SavedRun {
+        reactor: reactor.0.clone(),
+        rng: rng.snapshot(),
+        ticks: time.context().ticks(),
+        overstep: time.context().overstep(),
+        recording: recording.0.clone(),
+        neutrons: neutrons
+            .iter()
+            .filter_map(|(neutron, energy, origin, angle, distance, expiry)| {
+                Some(SavedNeutron {
+                    cell: fuel_rods.get(origin.0).ok()?.0,
+                    angle: angle.0,
+                    distance: distance.0,
+                    dying: *neutron == Neutron::Dying,
+                    energy: *energy,
+                    expiry: expiry.0.duration(),
+                    expiry_elapsed: expiry.0.elapsed(),
+                })
+            })
+            .collect(),
+    };
+
+    match saved.save(SAVE_PATH) {
+        Ok(()) => info!("saved run to {SAVE_PATH}"),
+        Err(err) => warn!("failed to save run to {SAVE_PATH}: {err}"),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_on_exit(mut commands: Commands, mut exit: EventReader<AppExit>) {
+    if exit.read().next().is_some() {
+        commands.trigger(SaveRun);
+    }
+}
+
+/// A run that has ended can't be continued.
+#[cfg(not(target_family = "wasm"))]
+fn delete_saved_run(
+    _: Trigger<crate::screens::game_over::GameOver>,
+    replay: Option<Res<ActiveReplay>>,
+) {
+    if replay.is_some() || !has_saved_run() {
+        return;
+    }
+    if let Err(err) = std::fs::remove_file(SAVE_PATH) {
+        warn!("failed to delete saved run {SAVE_PATH}: {err}");
+    }
+}
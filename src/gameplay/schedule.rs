@@ -21,12 +21,46 @@ pub fn plugin(app: &mut App) {
 pub struct Simulation {
     timestep: Duration,
     overstep: Duration,
+    ticks: u64,
+    requested_steps: u32,
+    stepping: bool,
 }
 
 impl Simulation {
     /// Corresponds to 1 Hz.
     const DEFAULT_TIMESTEP: Duration = Duration::from_millis(500);
 
+    /// Number of times the [`RunSimulation`] schedule has run so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Continues counting from a saved run.
+    pub fn resume(&mut self, ticks: u64, overstep: Duration) {
+        self.ticks = ticks;
+        self.overstep = overstep;
+    }
+
+    pub fn overstep(&self) -> Duration {
+        self.overstep
+    }
+
+    /// Runs a single tick on the next frame, even if the game is paused.
+    pub fn request_step(&mut self) {
+        self.requested_steps += 1;
+    }
+
+    /// Number of ticks requested with [`Simulation::request_step`] that haven't run yet.
+    pub fn requested_steps(&self) -> u32 {
+        self.requested_steps
+    }
+
+    /// Whether the tick that is currently running was requested with
+    /// [`Simulation::request_step`].
+    pub fn is_stepping(&self) -> bool {
+        self.stepping
+    }
+
     fn accumulate(&mut self, delta: Duration) {
         self.overstep += delta;
     }
@@ -37,10 +71,23 @@ impl Default for Simulation {
         Self {
             timestep: Simulation::DEFAULT_TIMESTEP,
             overstep: Duration::ZERO,
+            ticks: 0,
+            requested_steps: 0,
+            stepping: false,
         }
     }
 }
 
+/// Starts the simulation clock from zero, so tick numbers are comparable between runs.
+///
+/// Drops the time left over for the next physics step as well, so the physics steps fall on the
+/// same frames in every run.
+pub fn reset_simulation_clock(mut time: ResMut<Time<Simulation>>, mut fixed: ResMut<Time<Fixed>>) {
+    *time = Time::new_with(Simulation::default());
+    let overstep = fixed.overstep();
+    fixed.discard_overstep(overstep);
+}
+
 fn run_simulation_schedule(world: &mut World) {
     let delta = world.resource::<Time<Virtual>>().delta();
     world
@@ -59,11 +106,24 @@ fn run_simulation_schedule(world: &mut World) {
     *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
 }
 
+/// Run condition that lets paused systems run during a requested single step.
+pub fn simulation_stepping(time: Res<Time<Simulation>>) -> bool {
+    time.context().is_stepping()
+}
+
 fn expend_simulation(time: &mut Time<Simulation>) -> bool {
     let timestep = time.context().timestep;
-    if let Some(new_value) = time.context_mut().overstep.checked_sub(timestep) {
+    time.context_mut().stepping = false;
+    if time.context().requested_steps > 0 {
+        time.context_mut().requested_steps -= 1;
+        time.context_mut().stepping = true;
+        time.context_mut().ticks += 1;
+        time.advance_by(timestep);
+        true
+    } else if let Some(new_value) = time.context_mut().overstep.checked_sub(timestep) {
         // reduce accumulated and increase elapsed by period
         time.context_mut().overstep = new_value;
+        time.context_mut().ticks += 1;
         time.advance_by(timestep);
         true
     } else {
@@ -0,0 +1,167 @@
+//! The emergency shutdown, see [`ReactorState::scram`].
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::{menus::Menu, theme::palette::BUTTON_TEXT};
+
+const RESET_UNAVAILABLE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_scram_control);
+    app.add_systems(
+        Update,
+        (
+            scram.run_if(input_just_pressed(KeyCode::KeyX)),
+            reset_scram.run_if(input_just_pressed(KeyCode::KeyR)),
+        )
+            .run_if(in_state(Screen::Gameplay).and(in_state(Menu::None))),
+    );
+    app.add_systems(
+        Update,
+        update_scram_control.run_if(in_state(Screen::Gameplay).and(resource_exists::<Reactor>)),
+    );
+}
+
+/// Conditions that SCRAM the reactor without the player. Conditions that are `None` are off.
+#[derive(Clone, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoScram {
+    /// Pressure of any cell above which the reactor shuts down.
+    pub pressure: Option<usize>,
+    /// Temperature of any cell above which the reactor shuts down.
+    pub temperature: Option<f32>,
+}
+
+#[derive(Component)]
+struct ScramButton;
+
+#[derive(Component)]
+struct ResetScramButton;
+
+fn spawn_scram_control(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let button_mesh = meshes.add(Rectangle::new(96., 26.));
+
+    let root = commands
+        .spawn((
+            Name::new("SCRAM control"),
+            StateScoped(Screen::Gameplay),
+            Transform::from_xyz(-500., 320., 40.),
+            Visibility::default(),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Name::new("SCRAM"),
+            ScramButton,
+            ChildOf(root),
+            Mesh2d(button_mesh.clone()),
+            MeshMaterial2d(materials.add(Color::from(WARNING_COLOR))),
+            Transform::from_xyz(-50., 0., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![scram_label("SCRAM")],
+        ))
+        .observe(on_click_scram);
+    commands
+        .spawn((
+            Name::new("Reset SCRAM"),
+            ResetScramButton,
+            ChildOf(root),
+            Mesh2d(button_mesh),
+            MeshMaterial2d(materials.add(RESET_UNAVAILABLE_COLOR)),
+            Transform::from_xyz(50., 0., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![scram_label("reset")],
+        ))
+        .observe(on_click_reset_scram);
+}
+
+fn scram_label(text: &'static str) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text2d::new(text),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+        Transform::from_xyz(0., 0., 1.),
+        Pickable::IGNORE,
+    )
+}
+
+fn on_click_scram(_: Trigger<Pointer<Click>>, commands: Commands) {
+    scram(commands);
+}
+
+fn on_click_reset_scram(_: Trigger<Pointer<Click>>, commands: Commands, reactor: Res<Reactor>) {
+    reset_scram(commands, reactor);
+}
+
+fn scram(mut commands: Commands) {
+    commands.trigger(PlayerAction::Scram);
+}
+
+/// Resets a latched SCRAM, unless it's still cooling down.
+fn reset_scram(mut commands: Commands, reactor: Res<Reactor>) {
+    if reactor.scram.latched && reactor.scram.cooldown.is_zero() {
+        commands.trigger(PlayerAction::ResetScram);
+    }
+}
+
+/// Shows whether the SCRAM is latched and how long until it can be reset.
+fn update_scram_control(
+    reactor: Res<Reactor>,
+    scram_button: Single<&Children, With<ScramButton>>,
+    reset_button: Single<(&MeshMaterial2d<ColorMaterial>, &Children), With<ResetScramButton>>,
+    mut labels: Query<&mut Text2d>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let scram = &reactor.scram;
+    let (reset_material, reset_children) = *reset_button;
+
+    let scram_text = if scram.latched { "latched" } else { "SCRAM" };
+    let reset_text = if scram.latched && !scram.cooldown.is_zero() {
+        format!("reset {:.0}s", scram.cooldown.as_secs_f32().ceil())
+    } else {
+        "reset".to_string()
+    };
+    for (children, text) in [
+        (*scram_button, scram_text.to_string()),
+        (reset_children, reset_text),
+    ] {
+        for &entity in children.into_iter() {
+            match labels.get_mut(entity) {
+                Ok(mut label) if label.0 != text => label.0 = text.clone(),
+                _ => {}
+            }
+        }
+    }
+
+    let color = if scram.latched && scram.cooldown.is_zero() {
+        Color::WHITE
+    } else {
+        RESET_UNAVAILABLE_COLOR
+    };
+    // Touching the material every frame would re-upload it every frame.
+    if materials
+        .get(reset_material)
+        .is_none_or(|material| material.color == color)
+    {
+        return;
+    }
+    if let Some(material) = materials.get_mut(reset_material) {
+        material.color = color;
+    }
+}
@@ -0,0 +1,698 @@
+//! Operator scripts written in [Rhai](https://rhai.rs) and edited in the game.
+//!
+//! [`OperatorScript`] is a [`ReactorController`]. The script runs after every simulation tick
+//! with the [`Observation`] in a `state` constant, and issues [`PlayerAction`]s through a handful
+//! of registered functions. It can't reach anything else, and each run may only spend
+//! [`OPERATIONS_PER_TICK`] operations. See `assets/operator.rhai` for the API.
+
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    input::{
+        ButtonState, InputSystem,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+    sprite::Anchor,
+    text::TextBounds,
+};
+use rand::RngCore;
+use rhai::{
+    AST, Dynamic, Engine, FLOAT, INT, Map, Scope,
+    packages::{
+        BasicArrayPackage, BasicBlobPackage, BasicMapPackage, BasicMathPackage, BitFieldPackage,
+        CorePackage, LogicPackage, MoreStringPackage, Package,
+    },
+};
+
+use super::{legend::button_label, *};
+
+/// The script new players start with.
+pub const DEFAULT_SCRIPT: &str = include_str!("../../assets/operator.rhai");
+/// Where the script panel saves to, relative to the working directory. Kept apart from
+/// [`DEFAULT_SCRIPT`], which is built into the game.
+#[cfg(not(target_family = "wasm"))]
+const SCRIPT_SAVE_PATH: &str = "saves/operator.rhai";
+/// Rhai operations a script may spend per tick before it's stopped.
+pub const OPERATIONS_PER_TICK: u64 = 20_000;
+/// Lines of the script shown in the panel at once.
+const VISIBLE_LINES: usize = 26;
+const CODE_SIZE: Vec2 = Vec2::new(250., 470.);
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<OperatorScript>();
+    app.add_plugins(controller_plugin::<OperatorScript>);
+
+    app.add_systems(OnEnter(Screen::Gameplay), stop_script);
+}
+
+/// The script panel, which isn't part of headless runs.
+pub(super) fn ui_plugin(app: &mut App) {
+    app.init_resource::<ScriptEditor>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_script_panel);
+    app.add_systems(OnExit(Screen::Gameplay), stop_editing);
+    app.add_systems(
+        PreUpdate,
+        edit_script
+            .after(InputSystem)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        update_script_panel.run_if(
+            in_state(Screen::Gameplay)
+                .and(resource_changed::<OperatorScript>.or(resource_changed::<ScriptEditor>)),
+        ),
+    );
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScriptStatus {
+    #[default]
+    Stopped,
+    Running,
+    /// The script didn't compile, or failed or ran out of operations during a tick.
+    Failed(String),
+}
+
+/// An operator script and the engine that runs it.
+#[derive(Resource)]
+pub struct OperatorScript {
+    /// The text of the script, which only runs once it's reloaded after changing.
+    pub source: String,
+    pub status: ScriptStatus,
+    /// The last line the script printed.
+    pub output: Option<String>,
+    engine: Engine,
+    ast: Option<AST>,
+    actions: Arc<Mutex<Vec<PlayerAction>>>,
+    printed: Arc<Mutex<Option<String>>>,
+}
+
+impl Default for OperatorScript {
+    fn default() -> Self {
+        Self::new(load_script())
+    }
+}
+
+impl OperatorScript {
+    pub fn new(source: impl Into<String>) -> Self {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let printed = Arc::new(Mutex::new(None));
+
+        // The standard library without the clock, which would make runs depend on the time they
+        // happen at. A raw engine has no module resolver either, so scripts can't import files.
+        let mut engine = Engine::new_raw();
+        for package in [
+            CorePackage::new().as_shared_module(),
+            BitFieldPackage::new().as_shared_module(),
+            LogicPackage::new().as_shared_module(),
+            BasicMathPackage::new().as_shared_module(),
+            BasicArrayPackage::new().as_shared_module(),
+            BasicBlobPackage::new().as_shared_module(),
+            BasicMapPackage::new().as_shared_module(),
+            MoreStringPackage::new().as_shared_module(),
+        ] {
+            engine.register_global_module(package);
+        }
+        engine
+            .set_max_operations(OPERATIONS_PER_TICK)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1_000)
+            .set_max_array_size(1_000)
+            .set_max_map_size(100)
+            .disable_symbol("eval");
+        let printed_by_script = printed.clone();
+        engine.on_print(move |text| {
+            *printed_by_script.lock().unwrap() = Some(text.to_string());
+        });
+        register_commands(&mut engine, &actions);
+
+        Self {
+            source: source.into(),
+            status: ScriptStatus::Stopped,
+            output: None,
+            engine,
+            ast: None,
+            actions,
+            printed,
+        }
+    }
+
+    /// A script that's compiled and running, e.g. for headless runs.
+    pub fn running(source: impl Into<String>) -> Self {
+        let mut script = Self::new(source);
+        script.reload();
+        script
+    }
+
+    /// Compiles the source and starts running it.
+    pub fn reload(&mut self) {
+        match self.engine.compile(&self.source) {
+            Ok(ast) => {
+                self.ast = Some(ast);
+                self.status = ScriptStatus::Running;
+            }
+            Err(err) => {
+                self.ast = None;
+                self.status = ScriptStatus::Failed(err.to_string());
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.status = ScriptStatus::Stopped;
+    }
+}
+
+impl ReactorController for OperatorScript {
+    fn is_active(&self) -> bool {
+        self.status == ScriptStatus::Running
+    }
+
+    fn act(&mut self, observation: &Observation, _: &mut dyn RngCore) -> Vec<PlayerAction> {
+        let Some(ast) = &self.ast else {
+            return Vec::new();
+        };
+        let mut scope = Scope::new();
+        scope.push_constant("state", state_map(observation));
+        let result = self.engine.run_ast_with_scope(&mut scope, ast);
+
+        if let Some(text) = self.printed.lock().unwrap().take() {
+            self.output = Some(text);
+        }
+        // A run that fails halfway doesn't get to carry out half its commands.
+        let actions = std::mem::take(&mut *self.actions.lock().unwrap());
+        match result {
+            Ok(()) => actions,
+            Err(err) => {
+                self.status = ScriptStatus::Failed(err.to_string());
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn register_commands(engine: &mut Engine, actions: &Arc<Mutex<Vec<PlayerAction>>>) {
+    let push = |actions: &Arc<Mutex<Vec<PlayerAction>>>| {
+        let actions = actions.clone();
+        move |action: PlayerAction| actions.lock().unwrap().push(action)
+    };
+    // Negative indices can't name anything, the commands ignore them like unknown indices.
+    let index = |value: INT| usize::try_from(value).unwrap_or(usize::MAX);
+
+    let add = push(actions);
+    engine.register_fn("add_water", move |cell: INT| {
+        add(PlayerAction::AddWater { cell: index(cell) })
+    });
+    let add = push(actions);
+    engine.register_fn("distribute_water", move || {
+        add(PlayerAction::DistributeWater)
+    });
+    let add = push(actions);
+    engine.register_fn("increase_flow", move || {
+        add(PlayerAction::IncreaseWaterFlow)
+    });
+    let add = push(actions);
+    engine.register_fn("decrease_flow", move || {
+        add(PlayerAction::DecreaseWaterFlow)
+    });
+    let add = push(actions);
+    engine.register_fn("set_rod", move |rod: INT, percent: INT| {
+        add(PlayerAction::SetControlRodTarget {
+            rod: index(rod),
+            percent: percent.clamp(0, 100) as u8,
+        })
+    });
+    let add = push(actions);
+    engine.register_fn("toggle_rod", move |rod: INT| {
+        add(PlayerAction::ToggleControlRod { rod: index(rod) })
+    });
+    let add = push(actions);
+    engine.register_fn("refuel", move |cell: INT| {
+        add(PlayerAction::Refuel { cell: index(cell) })
+    });
+    let add = push(actions);
+    engine.register_fn("set_pump", move |cell: INT, rate: INT| {
+        add(PlayerAction::SetPumpRate {
+            cell: index(cell),
+            rate: rate.max(0) as usize,
+        })
+    });
+    let add = push(actions);
+    engine.register_fn("set_throttle", move |percent: INT| {
+        add(PlayerAction::SetThrottle {
+            percent: percent.clamp(0, 100) as u8,
+        })
+    });
+    let add = push(actions);
+    engine.register_fn("scram", move || add(PlayerAction::Scram));
+}
+
+/// The observation as the `state` constant of a script.
+fn state_map(observation: &Observation) -> Map {
+    let cells = observation
+        .cells
+        .iter()
+        .map(|cell| {
+            let mut map = Map::new();
+            map.insert("x".into(), Dynamic::from(cell.position.x as INT));
+            map.insert("y".into(), Dynamic::from(cell.position.y as INT));
+            map.insert("fuel".into(), Dynamic::from(cell.fuel.label().to_string()));
+            map.insert("water".into(), Dynamic::from(cell.water as INT));
+            map.insert("steam".into(), Dynamic::from(cell.steam as INT));
+            map.insert("pressure".into(), Dynamic::from(cell.pressure as INT));
+            map.insert(
+                "temperature".into(),
+                Dynamic::from(cell.temperature as FLOAT),
+            );
+            map.insert("pump".into(), Dynamic::from(cell.pump_rate as INT));
+            map.insert("pump_tripped".into(), Dynamic::from(cell.pump_tripped));
+            Dynamic::from_map(map)
+        })
+        .collect();
+    let rods = observation
+        .control_rods
+        .iter()
+        .map(|rod| {
+            let mut map = Map::new();
+            map.insert("x".into(), Dynamic::from(rod.position.x as INT));
+            map.insert("y".into(), Dynamic::from(rod.position.y as INT));
+            map.insert("insertion".into(), Dynamic::from(rod.insertion as FLOAT));
+            map.insert(
+                "target".into(),
+                rod.target
+                    .map_or(Dynamic::UNIT, |target| Dynamic::from(target as FLOAT)),
+            );
+            Dynamic::from_map(map)
+        })
+        .collect();
+
+    let phase = match observation.phase {
+        Phase::PowerGeneration => "power generation",
+        Phase::WaterFlow => "water flow",
+        Phase::NeutronRelease => "neutron release",
+        Phase::SteamVenting => "steam venting",
+    };
+
+    let mut state = Map::new();
+    state.insert("tick".into(), Dynamic::from(observation.tick as INT));
+    state.insert("phase".into(), Dynamic::from(phase.to_string()));
+    state.insert("cells".into(), Dynamic::from_array(cells));
+    state.insert("rods".into(), Dynamic::from_array(rods));
+    state.insert("water".into(), Dynamic::from(observation.water as INT));
+    state.insert(
+        "water_flow".into(),
+        Dynamic::from(observation.water_flow as INT),
+    );
+    state.insert("steam".into(), Dynamic::from(observation.steam as INT));
+    state.insert("energy".into(), Dynamic::from(observation.energy as INT));
+    state.insert("demand".into(), Dynamic::from(observation.demand as INT));
+    state.insert(
+        "demand_growth".into(),
+        Dynamic::from(observation.demand_growth as INT),
+    );
+    state.insert("scram".into(), Dynamic::from(observation.scram_latched));
+    let mut turbine = Map::new();
+    turbine.insert(
+        "throttle".into(),
+        Dynamic::from(observation.turbine.throttle as FLOAT),
+    );
+    turbine.insert(
+        "speed".into(),
+        Dynamic::from(observation.turbine.speed as FLOAT),
+    );
+    turbine.insert(
+        "load".into(),
+        Dynamic::from(observation.turbine.load as INT),
+    );
+    turbine.insert(
+        "efficiency".into(),
+        Dynamic::from(observation.turbine.efficiency as FLOAT),
+    );
+    turbine.insert("tripped".into(), Dynamic::from(observation.turbine.tripped));
+    state.insert("turbine".into(), Dynamic::from_map(turbine));
+    state
+}
+
+/// The script saved from the panel, or the default one.
+fn load_script() -> String {
+    #[cfg(not(target_family = "wasm"))]
+    if let Ok(source) = std::fs::read_to_string(SCRIPT_SAVE_PATH) {
+        return source;
+    }
+    DEFAULT_SCRIPT.to_string()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_script(source: &str) -> std::io::Result<()> {
+    if let Some(parent) = std::path::Path::new(SCRIPT_SAVE_PATH).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(SCRIPT_SAVE_PATH, source)
+}
+
+/// Stops the script for a new run, keeping its source.
+fn stop_script(mut script: ResMut<OperatorScript>) {
+    script.stop();
+    script.output = None;
+}
+
+/// Whether typing goes into the script rather than to the game's hotkeys, and where.
+#[derive(Resource, Default, PartialEq)]
+struct ScriptEditor {
+    focused: bool,
+    /// Byte offset in the source that typing goes to.
+    cursor: usize,
+    /// First line of the source shown in the panel.
+    scroll: usize,
+}
+
+impl ScriptEditor {
+    /// Moves the cursor back into `source` if it changed under the editor.
+    fn clamp(&mut self, source: &str) {
+        if self.cursor > source.len() || !source.is_char_boundary(self.cursor) {
+            self.cursor = source.len();
+        }
+    }
+
+    /// Scrolls the view just far enough to show the line with the cursor.
+    fn scroll_to_cursor(&mut self, source: &str) {
+        let line = source[..self.cursor].matches('\n').count();
+        if line < self.scroll {
+            self.scroll = line;
+        } else if line >= self.scroll + VISIBLE_LINES {
+            self.scroll = line + 1 - VISIBLE_LINES;
+        }
+    }
+
+    fn insert(&mut self, source: &mut String, text: &str) {
+        source.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    fn backspace(&mut self, source: &mut String) {
+        if let Some(c) = source[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            source.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self, source: &mut String) {
+        if self.cursor < source.len() {
+            source.remove(self.cursor);
+        }
+    }
+
+    fn left(&mut self, source: &str) {
+        if let Some(c) = source[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    fn right(&mut self, source: &str) {
+        if let Some(c) = source[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    fn home(&mut self, source: &str) {
+        self.cursor = line_start(source, self.cursor);
+    }
+
+    fn end(&mut self, source: &str) {
+        self.cursor = line_end(source, self.cursor);
+    }
+
+    /// Moves the cursor `lines` up or down, keeping its column where the line is long enough.
+    fn vertical(&mut self, source: &str, lines: isize) {
+        let start = line_start(source, self.cursor);
+        let column = source[start..self.cursor].chars().count();
+        let mut start = start;
+        for _ in 0..lines.unsigned_abs() {
+            if lines < 0 {
+                if start == 0 {
+                    break;
+                }
+                start = line_start(source, start - 1);
+            } else {
+                let end = line_end(source, start);
+                if end == source.len() {
+                    break;
+                }
+                start = end + 1;
+            }
+        }
+        let end = line_end(source, start);
+        self.cursor = source[start..end]
+            .char_indices()
+            .nth(column)
+            .map_or(end, |(offset, _)| start + offset);
+    }
+}
+
+fn line_start(source: &str, pos: usize) -> usize {
+    source[..pos].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn line_end(source: &str, pos: usize) -> usize {
+    source[pos..]
+        .find('\n')
+        .map_or(source.len(), |index| pos + index)
+}
+
+#[derive(Component)]
+struct ScriptPanel;
+
+#[derive(Component)]
+struct ScriptCode;
+
+#[derive(Component)]
+struct ScriptStatusText;
+
+fn spawn_script_panel(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let button_mesh = meshes.add(Rectangle::new(80., 26.));
+    let button_material = materials.add(Color::WHITE);
+
+    let root = legend::spawn_side_panel(
+        &mut commands,
+        "Script panel",
+        legend::SidePanel {
+            name: "script",
+            order: 3,
+        },
+        -50.,
+    );
+    commands.entity(root).insert(ScriptPanel);
+
+    commands
+        .spawn((
+            Name::new("Reload script"),
+            ChildOf(root),
+            Mesh2d(button_mesh.clone()),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(-75., -45., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("reload")],
+        ))
+        .observe(on_click_reload);
+    commands
+        .spawn((
+            Name::new("Stop script"),
+            ChildOf(root),
+            Mesh2d(button_mesh.clone()),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(15., -45., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("stop")],
+        ))
+        .observe(on_click_stop);
+    #[cfg(not(target_family = "wasm"))]
+    commands
+        .spawn((
+            Name::new("Save script"),
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(60., 26.))),
+            MeshMaterial2d(button_material),
+            Transform::from_xyz(95., -45., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![button_label("save")],
+        ))
+        .observe(on_click_save);
+
+    commands.spawn((
+        Name::new("Script status"),
+        ScriptStatusText,
+        ChildOf(root),
+        Text2d::default(),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        TextColor::WHITE,
+        TextBounds::new_horizontal(CODE_SIZE.x),
+        Anchor::TopLeft,
+        Transform::from_xyz(-CODE_SIZE.x / 2. + 5., -65., 1.),
+    ));
+
+    commands
+        .spawn((
+            Name::new("Script code"),
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::from_size(CODE_SIZE))),
+            MeshMaterial2d(materials.add(Color::srgb(0.1, 0.1, 0.12))),
+            Transform::from_xyz(5., -100. - CODE_SIZE.y / 2., 0.),
+            Pickable::default(),
+            children![(
+                Name::new("Code"),
+                ScriptCode,
+                Text2d::default(),
+                TextFont {
+                    font_size: 13.,
+                    ..default()
+                },
+                TextColor::WHITE,
+                TextBounds::new_horizontal(CODE_SIZE.x - 10.),
+                Anchor::TopLeft,
+                Transform::from_xyz(-CODE_SIZE.x / 2. + 5., CODE_SIZE.y / 2. - 5., 1.),
+                Pickable::IGNORE,
+            )],
+        ))
+        .observe(on_click_code)
+        .observe(on_scroll_code);
+}
+
+fn on_click_reload(
+    _: Trigger<Pointer<Click>>,
+    mut script: ResMut<OperatorScript>,
+    mut editor: ResMut<ScriptEditor>,
+) {
+    script.reload();
+    editor.focused = false;
+}
+
+fn on_click_stop(_: Trigger<Pointer<Click>>, mut script: ResMut<OperatorScript>) {
+    script.stop();
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn on_click_save(_: Trigger<Pointer<Click>>, mut script: ResMut<OperatorScript>) {
+    script.output = Some(match save_script(&script.source) {
+        Ok(()) => "saved".to_string(),
+        Err(err) => {
+            warn!("failed to save script to {SCRIPT_SAVE_PATH}: {err}");
+            err.to_string()
+        }
+    });
+}
+
+fn on_click_code(_: Trigger<Pointer<Click>>, mut editor: ResMut<ScriptEditor>) {
+    editor.focused = !editor.focused;
+}
+
+fn on_scroll_code(
+    trigger: Trigger<Pointer<Scroll>>,
+    script: Res<OperatorScript>,
+    mut editor: ResMut<ScriptEditor>,
+) {
+    let lines = script.source.split('\n').count();
+    let max_scroll = lines.saturating_sub(VISIBLE_LINES);
+    let scroll = trigger.event().y;
+    if scroll > 0. {
+        editor.scroll = editor.scroll.saturating_sub(3);
+    } else if scroll < 0. {
+        editor.scroll = (editor.scroll + 3).min(max_scroll);
+    }
+}
+
+fn stop_editing(mut editor: ResMut<ScriptEditor>) {
+    editor.focused = false;
+}
+
+/// Types into the script while the editor has focus, and keeps the keys from the game's hotkeys.
+fn edit_script(
+    mut events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut editor: ResMut<ScriptEditor>,
+    mut script: ResMut<OperatorScript>,
+    panel: Single<&Visibility, With<ScriptPanel>>,
+) {
+    if **panel == Visibility::Hidden && editor.focused {
+        editor.focused = false;
+    }
+    if !editor.focused {
+        events.clear();
+        return;
+    }
+
+    let editor = &mut *editor;
+    let source = &mut script.source;
+    editor.clamp(source);
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Escape => editor.focused = false,
+            Key::Enter => editor.insert(source, "\n"),
+            Key::Tab => editor.insert(source, "    "),
+            Key::Space => editor.insert(source, " "),
+            Key::Backspace => editor.backspace(source),
+            Key::Delete => editor.delete(source),
+            Key::ArrowLeft => editor.left(source),
+            Key::ArrowRight => editor.right(source),
+            Key::ArrowUp => editor.vertical(source, -1),
+            Key::ArrowDown => editor.vertical(source, 1),
+            Key::PageUp => editor.vertical(source, -(VISIBLE_LINES as isize)),
+            Key::PageDown => editor.vertical(source, VISIBLE_LINES as isize),
+            Key::Home => editor.home(source),
+            Key::End => editor.end(source),
+            Key::Character(text) => editor.insert(source, text),
+            _ => {}
+        }
+        editor.scroll_to_cursor(source);
+    }
+    keys.reset_all();
+}
+
+fn update_script_panel(
+    script: Res<OperatorScript>,
+    editor: Res<ScriptEditor>,
+    mut code: Single<&mut Text2d, (With<ScriptCode>, Without<ScriptStatusText>)>,
+    mut status: Single<(&mut Text2d, &mut TextColor), With<ScriptStatusText>>,
+) {
+    let mut source = script.source.clone();
+    if editor.focused && editor.cursor <= source.len() && source.is_char_boundary(editor.cursor) {
+        source.insert(editor.cursor, '|');
+    }
+    code.0 = source
+        .split('\n')
+        .skip(editor.scroll)
+        .take(VISIBLE_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let (status_text, status_color) = &mut *status;
+    let (text, color) = match &script.status {
+        ScriptStatus::Stopped => ("stopped".to_string(), Color::WHITE),
+        ScriptStatus::Running => ("running".to_string(), Color::WHITE),
+        ScriptStatus::Failed(err) => (err.clone(), Color::from(WARNING_COLOR)),
+    };
+    status_text.0 = match &script.output {
+        Some(output) => format!("{text}: {output}"),
+        None => text,
+    };
+    status_color.0 = color;
+}
@@ -1,14 +1,9 @@
-use std::f32::consts::TAU;
-
 use avian2d::prelude::{CollisionEventsEnabled, LayerMask};
 use bevy::prelude::*;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::*;
-use crate::{
-    PausableSystems,
-    screens::game_over::{GameOver, GameOverCause},
-};
+use crate::{PausableSystems, screens::game_over::GameOver};
 
 pub fn plugin(app: &mut App) {
     app.init_state::<Phase>();
@@ -31,28 +26,44 @@ pub fn plugin(app: &mut App) {
             .in_set(PausableSystems),
     );
 
-    app.add_systems(RunSimulation, next_phase.run_if(in_state(Screen::Gameplay)));
-
-    app.add_systems(RunSimulation, create_water.in_set(PhaseSystems::WaterFlow));
     app.add_systems(
         RunSimulation,
-        launch_neutrons.in_set(PhaseSystems::NeutronRelease),
+        (advance_reactor, step_reactor)
+            .chain()
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(PausableSystems),
     );
+
     app.add_systems(
-        RunSimulation,
-        (vent_steam, turn_uranium_into_xenon, track_cell_pressure)
-            .in_set(PhaseSystems::SteamVenting),
+        Update,
+        (
+            sync_cells,
+            sync_fuel_rods,
+            sync_control_rods,
+            sync_containers,
+            sync_water_flow,
+            sync_power_demand,
+            sync_turbine,
+        )
+            .run_if(in_state(Screen::Gameplay)),
     );
-
     app.add_systems(
         Update,
-        handle_overpressure_timer.run_if(in_state(Screen::Gameplay)),
+        (track_cell_pressure, handle_overpressure_timer)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
     );
 
     app.add_observer(on_launch_neutron);
 }
 
-#[derive(States, Clone, Copy, Reflect, Default, Debug, Eq, PartialEq, Hash)]
+/// The reactor model of the current run, which the gameplay entities mirror.
+#[derive(Resource, Deref, DerefMut)]
+pub struct Reactor(pub ReactorState);
+
+#[derive(
+    States, Clone, Copy, Reflect, Default, Debug, Eq, PartialEq, Hash, Serialize, Deserialize,
+)]
 pub enum Phase {
     #[default]
     PowerGeneration,
@@ -62,7 +73,7 @@ pub enum Phase {
 }
 
 impl Phase {
-    fn next(&self) -> Self {
+    pub fn next(&self) -> Self {
         match *self {
             Self::PowerGeneration => Self::WaterFlow,
             Self::WaterFlow => Self::NeutronRelease,
@@ -70,6 +81,15 @@ impl Phase {
             Self::SteamVenting => Self::PowerGeneration,
         }
     }
+
+    pub fn previous(&self) -> Self {
+        match *self {
+            Self::PowerGeneration => Self::SteamVenting,
+            Self::WaterFlow => Self::PowerGeneration,
+            Self::NeutronRelease => Self::WaterFlow,
+            Self::SteamVenting => Self::NeutronRelease,
+        }
+    }
 }
 
 #[derive(SystemSet, Clone, Copy, Reflect, Debug, Hash, PartialEq, Eq)]
@@ -80,12 +100,177 @@ pub enum PhaseSystems {
     SteamVenting,
 }
 
-fn next_phase(state: Res<State<Phase>>, mut next: ResMut<NextState<Phase>>) {
-    next.set(state.next());
+pub(super) fn step_reactor(
+    mut commands: Commands,
+    mut reactor: ResMut<Reactor>,
+    mut rng: ResMut<SimulationRng>,
+    mut next_phase: ResMut<NextState<Phase>>,
+    cells: Query<(Entity, &CellIndex), With<Cell>>,
+    fuel_rods: Query<(Entity, &CellIndex), With<FuelRod>>,
+) {
+    let events = reactor.step(&mut rng);
+    next_phase.set(reactor.phase);
+    if events.is_empty() {
+        return;
+    }
+
+    let cells = by_cell_index(&cells);
+    let fuel_rods = by_cell_index(&fuel_rods);
+    let at = |entities: &[Option<Entity>], cell: usize| entities.get(cell).copied().flatten();
+
+    for event in events {
+        match event {
+            ReactorEvent::NeutronReleased { cell, angle } => {
+                if let Some(origin) = at(&fuel_rods, cell) {
+                    commands.trigger(LaunchNeutron { origin, angle });
+                }
+            }
+            ReactorEvent::SteamVented { cell, count } => {
+                if let Some(entity) = at(&cells, cell) {
+                    commands.trigger_targets(VentSteamParticlesFromCell(count), entity);
+                }
+            }
+            ReactorEvent::WaterBoiled { cell, count } => {
+                if let Some(entity) = at(&cells, cell) {
+                    commands.trigger_targets(BoilWaterParticlesInCell(count), entity);
+                }
+            }
+            ReactorEvent::WaterPumped { cell, count } => {
+                if let Some(entity) = at(&cells, cell) {
+                    commands.trigger_targets(PumpWaterParticlesIntoCell(count), entity);
+                }
+            }
+            ReactorEvent::PowerGenerated(count) => {
+                commands.trigger(GenerateEnergyParticles(count));
+            }
+            ReactorEvent::Failed(cause) => {
+                commands.trigger(GameOver {
+                    cause,
+                    power_generated: reactor.energy,
+                    difficulty: reactor.difficulty,
+                });
+            }
+        }
+    }
+}
+
+/// The entities of a query laid out by their [`CellIndex`], so events find theirs directly.
+fn by_cell_index<F: bevy::ecs::query::QueryFilter>(
+    query: &Query<(Entity, &CellIndex), F>,
+) -> Vec<Option<Entity>> {
+    let mut entities = Vec::new();
+    for (entity, index) in query {
+        if entities.len() <= index.0 {
+            entities.resize(index.0 + 1, None);
+        }
+        entities[index.0] = Some(entity);
+    }
+    entities
+}
+
+/// Runs once per tick with the length of a tick as the delta, so the rods, the demand and the
+/// SCRAM cooldown end up the same no matter how the frames fell.
+fn advance_reactor(time: Res<Time>, mut reactor: ResMut<Reactor>) {
+    reactor.advance(time.delta());
+}
+
+fn sync_cells(
+    reactor: Res<Reactor>,
+    mut cells: Query<(&CellIndex, &mut ParticleCount), With<Cell>>,
+) {
+    for (index, mut particle_count) in &mut cells {
+        let Some(cell) = reactor.cells.get(index.0) else {
+            continue;
+        };
+        if particle_count.get() != cell.pressure() {
+            particle_count.set(cell.pressure());
+        }
+    }
+}
+
+fn sync_fuel_rods(
+    reactor: Res<Reactor>,
+    mut fuel_rods: Query<(&CellIndex, &mut FuelRod, &mut Reactivity, &mut Refuelling)>,
+) {
+    for (index, mut fuel_rod, mut reactivity, mut refuelling) in &mut fuel_rods {
+        if let Some(cell) = reactor.cells.get(index.0) {
+            fuel_rod.set_if_neq(cell.fuel);
+            reactivity.set_if_neq(Reactivity(cell.reactivity(&reactor.balance)));
+            refuelling.set_if_neq(Refuelling((cell.refuelling > 0).then_some(cell.loaded)));
+        }
+    }
 }
 
-fn create_water(mut commands: Commands) {
-    commands.trigger(CreateWaterParticles(WATER_CREATED_PER_TICK));
+fn sync_control_rods(
+    mut commands: Commands,
+    reactor: Res<Reactor>,
+    mut control_rods: Query<(
+        Entity,
+        &ControlRodIndex,
+        &mut ControlRodInsertion,
+        &mut ControlRodTarget,
+        Option<&ControlRodMovement>,
+    )>,
+) {
+    for (entity, index, mut insertion, mut target, movement) in &mut control_rods {
+        let Some(rod) = reactor.control_rods.get(index.0) else {
+            continue;
+        };
+        insertion.set_if_neq(ControlRodInsertion(rod.insertion));
+        target.set_if_neq(ControlRodTarget(rod.target));
+        if movement.copied() != rod.movement {
+            match rod.movement {
+                Some(movement) => {
+                    commands.entity(entity).try_insert(movement);
+                }
+                None => {
+                    commands.entity(entity).try_remove::<ControlRodMovement>();
+                }
+            }
+        }
+    }
+}
+
+fn sync_containers(reactor: Res<Reactor>, mut containers: Query<&mut ParticleContainer>) {
+    for mut container in &mut containers {
+        let count = match container.particle {
+            Particle::Water(_) => reactor.water,
+            Particle::Steam => reactor.steam,
+            Particle::Energy => reactor.energy,
+        };
+        if container.count != count {
+            container.count = count;
+        }
+    }
+}
+
+fn sync_water_flow(
+    reactor: Res<Reactor>,
+    mut water_flow: Single<&mut WaterFlow, With<WaterContainer>>,
+) {
+    if water_flow.get() != reactor.water_flow {
+        water_flow.set(reactor.water_flow);
+    }
+}
+
+fn sync_power_demand(
+    reactor: Res<Reactor>,
+    query: Single<(&mut PowerDemand, &mut NextPowerDemand), With<EnergyContainer>>,
+) {
+    let (mut demand, mut next) = query.into_inner();
+    demand.set_if_neq(PowerDemand(reactor.demand.demand));
+    next.set_if_neq(NextPowerDemand {
+        delta: reactor.demand.delta,
+    });
+}
+
+fn sync_turbine(reactor: Res<Reactor>, mut turbine: Single<&mut Turbine, With<TurbineContainer>>) {
+    turbine.set_if_neq(Turbine {
+        throttle: reactor.turbine.throttle,
+        load_ratio: reactor.turbine.load_ratio(),
+        efficiency: reactor.turbine.efficiency,
+        tripped: reactor.turbine.tripped,
+    });
 }
 
 #[derive(Event, Clone, Copy, Reflect)]
@@ -97,97 +282,35 @@ pub struct LaunchNeutron {
 fn on_launch_neutron(
     trigger: Trigger<LaunchNeutron>,
     mut commands: Commands,
+    reactor: Res<Reactor>,
     transforms: Query<&GlobalTransform, With<FuelRod>>,
 ) -> Result {
     let transform = transforms.get(trigger.origin)?.translation();
+    commands.spawn(neutron(
+        trigger.origin,
+        trigger.angle,
+        transform.xy(),
+        reactor.balance.neutron_lifetime_sec,
+    ));
+    Ok(())
+}
+
+/// A freshly launched neutron flying away from `origin`.
+pub fn neutron(origin: Entity, angle: f32, translation: Vec2, lifetime_sec: f32) -> impl Bundle {
     let mut layer_mask = LayerMask::ALL;
     layer_mask.remove(GameLayer::Neutron);
-    commands.spawn((
+    (
         Name::new("Neutron"),
         Neutron::default(),
-        Expiry(Timer::from_seconds(NEUTRON_LIFETIME_SEC, TimerMode::Once)),
-        CurrentAngle(trigger.angle),
-        Origin(trigger.origin),
-        Transform::from_xyz(transform.x, transform.y, 25.),
+        Expiry(Timer::from_seconds(lifetime_sec, TimerMode::Once)),
+        CurrentAngle(angle),
+        Origin(origin),
+        Transform::from_xyz(translation.x, translation.y, 25.),
         RigidBody::Kinematic,
         Collider::circle(NEUTRON_RADIUS),
         CollisionLayers::new(GameLayer::Neutron, layer_mask),
         CollisionEventsEnabled,
-    ));
-    Ok(())
-}
-
-fn launch_neutrons(mut commands: Commands, fuel_rods: Query<(Entity, &FuelRod)>) {
-    let mut rng = rand::rng();
-    for (entity, &fuel_rod) in &fuel_rods {
-        if fuel_rod != FuelRod::Uranium {
-            continue;
-        }
-
-        for _ in 0..MAX_NEUTRONS_RELEASED_PER_TICK {
-            if rng.random_range(0.0..1.0) < NEUTRON_SPAWN_CHANCE {
-                commands.trigger(LaunchNeutron {
-                    origin: entity,
-                    angle: rng.random_range(0.0..TAU),
-                });
-            }
-        }
-    }
-}
-
-fn turn_uranium_into_xenon(mut commands: Commands, fuel_rods: Query<(Entity, &FuelRod)>) {
-    let mut rng = rand::rng();
-    for (entity, &fuel_rod) in &fuel_rods {
-        if fuel_rod == FuelRod::Xenon {
-            continue;
-        }
-
-        if rng.random_range(0.0..1.0) < XENON_SPAWN_CHANCE_PER_TICK {
-            commands.entity(entity).try_insert(FuelRod::Xenon);
-        }
-    }
-}
-
-fn vent_steam(
-    mut commands: Commands,
-    steam_particles: Query<(Entity, &GlobalTransform, &Lifetime), With<Particle>>,
-    mut cells: Query<(&mut ParticleCount, &Children)>,
-    container: Single<(Entity, &GlobalTransform), With<SteamContainer>>,
-) {
-    let (container_entity, container_transform) = container.into_inner();
-
-    for (mut particle_count, children) in &mut cells {
-        let mut particles: Vec<_> = children
-            .iter()
-            .flat_map(|entity| steam_particles.get(entity))
-            .collect();
-        particles.sort_by(|(_, _, a), (_, _, b)| {
-            b.0.elapsed()
-                .partial_cmp(&a.0.elapsed())
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        let vented_particles = particles.into_iter().take(STEAM_VENTED_PER_TICK);
-        for (entity, particle_transform, _) in vented_particles {
-            particle_count.decrement(1);
-
-            let new_transform = particle_transform.reparented_to(container_transform);
-            commands
-                .entity(entity)
-                .try_remove::<Lifetime>()
-                .try_remove::<TargetAngle>()
-                .try_remove::<EasedMotion>()
-                .try_remove::<InCell>()
-                .try_insert((ChildOf(container_entity), new_transform));
-            commands.trigger_targets(
-                MoveParticle {
-                    to: Vec2::new(0.0, 0.0),
-                    stop_current: false,
-                },
-                entity,
-            );
-        }
-    }
+    )
 }
 
 #[derive(Component)]
@@ -195,23 +318,25 @@ struct OverPressureTimer(Timer);
 
 fn track_cell_pressure(
     mut commands: Commands,
-    query: Query<(Entity, &ParticleCount), With<Cell>>,
-    energy_container: Single<&ParticleContainer, With<EnergyContainer>>,
+    reactor: Res<Reactor>,
+    query: Query<(Entity, &CellIndex, Has<OverPressureTimer>), With<Cell>>,
 ) {
-    for (entity, particle_count) in &query {
-        if particle_count.get() > PRESSURE_EXPLOSION_LEVEL {
-            commands.trigger(GameOver {
-                cause: GameOverCause::Explosion,
-                power_generated: energy_container.count,
-            });
-        } else if particle_count.get() > PRESSURE_WARN_LEVEL {
-            commands
-                .entity(entity)
-                .try_insert(OverPressureTimer(Timer::from_seconds(
-                    1.0,
-                    TimerMode::Repeating,
-                )));
-        } else {
+    for (entity, index, warning) in &query {
+        let Some(cell) = reactor.cells.get(index.0) else {
+            continue;
+        };
+        if cell.pressure() > reactor.balance.pressure_warn_level
+            || cell.temperature > reactor.balance.overheat_warn_temperature
+        {
+            if !warning {
+                commands
+                    .entity(entity)
+                    .try_insert(OverPressureTimer(Timer::from_seconds(
+                        1.0,
+                        TimerMode::Repeating,
+                    )));
+            }
+        } else if warning {
             commands
                 .entity(entity)
                 .try_remove::<OverPressureTimer>()
@@ -223,19 +348,19 @@ fn track_cell_pressure(
 fn handle_overpressure_timer(
     mut commands: Commands,
     time: Res<Time>,
-    query: Single<(Entity, &mut OverPressureTimer)>,
+    mut query: Query<(Entity, &mut OverPressureTimer)>,
 ) {
-    let (entity, mut timer) = query.into_inner();
-
-    timer.0.tick(time.delta());
-
-    let t = timer.0.fraction();
-    let blend = if t <= 0.5 {
-        t / 0.5 // 0.0 -> 1.0
-    } else {
-        1.0 - ((t - 0.5) / 0.5) // 1.0 -> 0.0
-    };
-    commands.entity(entity).try_insert(CellColor(
-        Color::from(WARNING_COLOR).mix(&CELL_COLOR.into(), blend),
-    ));
+    for (entity, mut timer) in &mut query {
+        timer.0.tick(time.delta());
+
+        let t = timer.0.fraction();
+        let blend = if t <= 0.5 {
+            t / 0.5 // 0.0 -> 1.0
+        } else {
+            1.0 - ((t - 0.5) / 0.5) // 1.0 -> 0.0
+        };
+        commands.entity(entity).try_insert(CellColor(
+            Color::from(WARNING_COLOR).mix(&CELL_COLOR.into(), blend),
+        ));
+    }
 }
@@ -0,0 +1,247 @@
+//! Controls for how fast the simulation runs.
+//!
+//! The speed scales [`Time<Virtual>`], which drives both the simulation clock and everything that
+//! moves in `Update`, so neutrons keep up with the ticks at any speed. A replay plays back the
+//! frames of its run, so it runs at the speeds its run did.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use super::*;
+use crate::{Pause, menus::Menu, theme::palette::BUTTON_TEXT};
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<SimulationSpeed>();
+    app.init_resource::<SimulationSpeed>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_speed_control);
+    app.add_systems(OnExit(Screen::Gameplay), reset_speed);
+    app.add_systems(OnEnter(Pause(true)), pause_virtual_time);
+    app.add_systems(OnEnter(Pause(false)), unpause_virtual_time);
+
+    app.add_systems(
+        Update,
+        (
+            select_speed_with_keys,
+            toggle_pause.run_if(input_just_pressed(KeyCode::Space)),
+            step.run_if(input_just_pressed(KeyCode::KeyN)),
+        )
+            .run_if(in_state(Screen::Gameplay).and(in_state(Menu::None))),
+    );
+    app.add_systems(
+        Update,
+        (apply_speed, update_speed_buttons)
+            .run_if(in_state(Screen::Gameplay).and(resource_changed::<SimulationSpeed>)),
+    );
+}
+
+#[derive(Resource, Clone, Copy, Default, Debug, Eq, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub enum SimulationSpeed {
+    Half,
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+}
+
+impl SimulationSpeed {
+    pub const ALL: [Self; 4] = [Self::Half, Self::Normal, Self::Double, Self::Quadruple];
+
+    pub fn factor(&self) -> f32 {
+        match self {
+            Self::Half => 0.5,
+            Self::Normal => 1.0,
+            Self::Double => 2.0,
+            Self::Quadruple => 4.0,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Half => "0.5x",
+            Self::Normal => "1x",
+            Self::Double => "2x",
+            Self::Quadruple => "4x",
+        }
+    }
+
+    fn key(&self) -> KeyCode {
+        match self {
+            Self::Half => KeyCode::Digit1,
+            Self::Normal => KeyCode::Digit2,
+            Self::Double => KeyCode::Digit3,
+            Self::Quadruple => KeyCode::Digit4,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct SpeedButton(SimulationSpeed);
+
+fn spawn_speed_control(
+    mut commands: Commands,
+    speed: Res<SimulationSpeed>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let button_mesh = meshes.add(Rectangle::new(44., 26.));
+    let wide_button_mesh = meshes.add(Rectangle::new(96., 26.));
+    let button_material = materials.add(Color::WHITE);
+
+    let root = commands
+        .spawn((
+            Name::new("Speed control"),
+            StateScoped(Screen::Gameplay),
+            Transform::from_xyz(-500., -330., 40.),
+            Visibility::default(),
+        ))
+        .id();
+
+    for (i, option) in SimulationSpeed::ALL.into_iter().enumerate() {
+        commands
+            .spawn((
+                Name::new(format!("Speed {}", option.label())),
+                ChildOf(root),
+                SpeedButton(option),
+                Mesh2d(button_mesh.clone()),
+                MeshMaterial2d(materials.add(speed_button_color(option, *speed))),
+                Transform::from_xyz(-75. + i as f32 * 50., 17., 1.),
+                Pickable::default(),
+                PlaysClickSound,
+                PlaysHoverSound,
+                children![speed_label(option.label())],
+            ))
+            .observe(on_click_speed);
+    }
+
+    commands
+        .spawn((
+            Name::new("Pause"),
+            ChildOf(root),
+            Mesh2d(wide_button_mesh.clone()),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(-50., -17., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![speed_label("pause")],
+        ))
+        .observe(on_click_pause);
+    commands
+        .spawn((
+            Name::new("Step"),
+            ChildOf(root),
+            Mesh2d(wide_button_mesh.clone()),
+            MeshMaterial2d(button_material.clone()),
+            Transform::from_xyz(50., -17., 1.),
+            Pickable::default(),
+            PlaysClickSound,
+            PlaysHoverSound,
+            children![speed_label("step")],
+        ))
+        .observe(on_click_step);
+}
+
+fn speed_label(text: &'static str) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text2d::new(text),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+        Transform::from_xyz(0., 0., 1.),
+        Pickable::IGNORE,
+    )
+}
+
+fn speed_button_color(option: SimulationSpeed, current: SimulationSpeed) -> Color {
+    if option == current {
+        URANIUM_COLOR
+    } else {
+        Color::WHITE
+    }
+}
+
+fn on_click_speed(
+    trigger: Trigger<Pointer<Click>>,
+    buttons: Query<&SpeedButton>,
+    mut speed: ResMut<SimulationSpeed>,
+) {
+    if let Ok(button) = buttons.get(trigger.target()) {
+        speed.set_if_neq(button.0);
+    }
+}
+
+fn on_click_pause(
+    _: Trigger<Pointer<Click>>,
+    pause: Res<State<Pause>>,
+    next_pause: ResMut<NextState<Pause>>,
+) {
+    toggle_pause(pause, next_pause);
+}
+
+fn on_click_step(
+    _: Trigger<Pointer<Click>>,
+    pause: Res<State<Pause>>,
+    time: ResMut<Time<Simulation>>,
+    replay: Option<Res<ActiveReplay>>,
+) {
+    step(pause, time, replay);
+}
+
+fn select_speed_with_keys(input: Res<ButtonInput<KeyCode>>, mut speed: ResMut<SimulationSpeed>) {
+    for option in SimulationSpeed::ALL {
+        if input.just_pressed(option.key()) {
+            speed.set_if_neq(option);
+        }
+    }
+}
+
+/// Pauses without opening the pause menu, so the reactor stays visible for stepping.
+fn toggle_pause(pause: Res<State<Pause>>, mut next_pause: ResMut<NextState<Pause>>) {
+    next_pause.set(Pause(!pause.get().0));
+}
+
+/// Runs a single simulation phase while paused. A replay steps the way its run did.
+fn step(
+    pause: Res<State<Pause>>,
+    mut time: ResMut<Time<Simulation>>,
+    replay: Option<Res<ActiveReplay>>,
+) {
+    if pause.get().0 && replay.is_none() {
+        time.context_mut().request_step();
+    }
+}
+
+fn apply_speed(speed: Res<SimulationSpeed>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(speed.factor());
+}
+
+fn update_speed_buttons(
+    speed: Res<SimulationSpeed>,
+    buttons: Query<(&SpeedButton, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (button, material) in &buttons {
+        if let Some(material) = materials.get_mut(material) {
+            material.color = speed_button_color(button.0, *speed);
+        }
+    }
+}
+
+fn reset_speed(mut speed: ResMut<SimulationSpeed>, mut time: ResMut<Time<Virtual>>) {
+    *speed = SimulationSpeed::default();
+    time.set_relative_speed(1.0);
+}
+
+fn pause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
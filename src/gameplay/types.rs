@@ -1,13 +1,14 @@
-use std::time::Duration;
-
 use avian2d::prelude::PhysicsLayer;
 use bevy::{platform::collections::HashMap, prelude::*, time::Stopwatch};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::*;
 
 pub fn plugin(app: &mut App) {
     app.register_type::<Neutron>();
+    app.register_type::<NeutronEnergy>();
+    app.register_type::<ModeratedBy>();
     app.register_type::<Particle>();
     app.register_type::<Expiry>();
     app.register_type::<Lifetime>();
@@ -37,13 +38,38 @@ impl Particle {
 
 #[derive(Component, Clone, Reflect, Default, Eq, PartialEq)]
 #[reflect(Component)]
-#[require(CurrentAngle, CurrentDistance)]
+#[require(CurrentAngle, CurrentDistance, NeutronEnergy)]
 pub enum Neutron {
     #[default]
     Active,
     Dying,
 }
 
+/// The energy group of a neutron. Fission releases fast neutrons, water slows them down.
+#[derive(
+    Component, Clone, Copy, Default, Debug, Eq, PartialEq, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component)]
+pub enum NeutronEnergy {
+    #[default]
+    Fast,
+    Thermal,
+}
+
+impl NeutronEnergy {
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Fast => NEUTRON_COLOR.into(),
+            Self::Thermal => THERMAL_NEUTRON_COLOR.into(),
+        }
+    }
+}
+
+/// The water particle that slowed a neutron down, which can't absorb it right after.
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct ModeratedBy(pub Entity);
+
 #[derive(Component, Clone, Reflect)]
 #[reflect(Component)]
 pub struct Expiry(pub Timer);
@@ -95,21 +121,13 @@ impl ParticleCount {
         self.0
     }
 
-    pub fn increment(&mut self, amount: usize) {
-        self.0 += amount;
-    }
-
-    pub fn decrement(&mut self, amount: usize) {
-        if amount > self.0 {
-            self.0 = 0;
-        } else {
-            self.0 -= amount;
-        }
+    pub fn set(&mut self, amount: usize) {
+        self.0 = amount;
     }
 }
 
 #[derive(Component, Clone, Copy, Reflect)]
-#[require(ParticleCount, CurrentScale, CellColor)]
+#[require(ParticleCount, CurrentScale, CellColor, Pump)]
 #[reflect(Component)]
 pub struct Cell(pub Position);
 
@@ -131,11 +149,16 @@ pub struct InCell;
 #[reflect(Component)]
 pub struct ControlRod(pub Position);
 
-#[derive(Component, Clone, Copy, Reflect)]
+#[derive(Component, Clone, Copy, Reflect, PartialEq)]
 #[reflect(Component)]
 pub struct ControlRodInsertion(pub f32);
 
-#[derive(Component, Clone, Copy, Reflect)]
+/// The insertion a control rod is moving to, if it's moving.
+#[derive(Component, Clone, Copy, Reflect, Default, PartialEq)]
+#[reflect(Component)]
+pub struct ControlRodTarget(pub Option<f32>);
+
+#[derive(Component, Clone, Copy, Reflect, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[reflect(Component)]
 pub enum ControlRodMovement {
     Up,
@@ -143,6 +166,14 @@ pub enum ControlRodMovement {
 }
 
 impl ControlRodMovement {
+    /// The insertion at the end of the range the rod moves towards.
+    pub fn end(&self) -> f32 {
+        match self {
+            Self::Up => 1.0,
+            Self::Down => 0.0,
+        }
+    }
+
     pub fn reverse(&mut self) {
         *self = match self {
             Self::Up => Self::Down,
@@ -151,16 +182,47 @@ impl ControlRodMovement {
     }
 }
 
-#[derive(Component, Clone, Copy, Reflect, Eq, PartialEq)]
+/// What a fuel rod is made of. [`FuelTypes`] describes how each fuel behaves, except for xenon and
+/// spent fuel which are what any fuel turns into when it's poisoned or burned up.
+#[derive(
+    Component, Clone, Copy, Default, Reflect, Debug, Eq, PartialEq, Hash, Serialize, Deserialize,
+)]
 #[reflect(Component)]
 pub enum FuelRod {
+    #[default]
     Uranium,
     Xenon,
+    /// Burned up fuel that has to be replaced.
+    Spent,
+    Plutonium,
+    /// Fertile fuel that doesn't split but breeds fissile fuel from the neutrons it catches.
+    Thorium,
+    /// Mixed uranium and plutonium oxides.
+    Mox,
 }
 
 impl FuelRod {
-    pub fn random(uranium_chance: f32) -> Self {
-        let mut rng = rand::rng();
+    pub const ALL: [Self; 6] = [
+        Self::Uranium,
+        Self::Plutonium,
+        Self::Thorium,
+        Self::Mox,
+        Self::Xenon,
+        Self::Spent,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Uranium => "uranium",
+            Self::Xenon => "xenon",
+            Self::Spent => "spent fuel",
+            Self::Plutonium => "plutonium",
+            Self::Thorium => "thorium",
+            Self::Mox => "mox",
+        }
+    }
+
+    pub fn random(rng: &mut impl Rng, uranium_chance: f32) -> Self {
         if rng.random_range(0.0..1.0) < uranium_chance {
             Self::Uranium
         } else {
@@ -181,39 +243,46 @@ pub struct ControlRodInsertionIndicator;
 #[reflect(Component)]
 pub struct CellIndex(pub usize);
 
-#[derive(Component, Clone, Copy, Reflect, Default)]
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct ControlRodIndex(pub usize);
+
+#[derive(Component, Clone, Copy, Reflect, Default, PartialEq)]
 #[reflect(Component)]
 pub struct Reactivity(pub f32);
 
-#[derive(Component, Clone, Copy, Reflect, Default)]
+/// The fuel going into a cell whose spent fuel rod is being replaced.
+#[derive(Component, Clone, Copy, Reflect, Default, PartialEq)]
+#[reflect(Component)]
+pub struct Refuelling(pub Option<FuelRod>);
+
+#[derive(Component, Clone, Copy, Reflect, Default, PartialEq)]
 pub struct PowerDemand(pub usize);
 
-#[derive(Component, Clone, Reflect)]
+/// The turbine as the reactor model has it, see [`TurbineState`].
+#[derive(Component, Clone, Copy, Reflect, Default, PartialEq)]
+#[reflect(Component)]
+pub struct Turbine {
+    pub throttle: f32,
+    /// The speed measured against the load, see [`TurbineState::load_ratio`].
+    pub load_ratio: f32,
+    pub efficiency: f32,
+    /// Ticks left until the turbine restarts, zero while it runs.
+    pub tripped: usize,
+}
+
+#[derive(Component, Clone, Copy, Reflect, PartialEq)]
 pub struct NextPowerDemand {
     pub delta: usize,
-    pub demand_timer: Timer,
-    pub demand_rate_timer: Timer,
-    pub tutorial_timer: Timer,
 }
 
 impl Default for NextPowerDemand {
     fn default() -> Self {
-        Self {
-            delta: 1,
-            demand_timer: Timer::new(
-                Duration::from_secs_f32(INCREASE_POWER_DEMAND_SEC),
-                TimerMode::Repeating,
-            ),
-            demand_rate_timer: Timer::new(
-                Duration::from_secs_f32(INCREASE_POWER_DEMAND_INCREASE_RATE_SEC),
-                TimerMode::Repeating,
-            ),
-            tutorial_timer: Timer::new(Duration::from_secs_f32(TUTORIAL_SEC), TimerMode::Once),
-        }
+        Self { delta: 1 }
     }
 }
 
-#[derive(Clone, Copy, Default, Reflect, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Default, Reflect, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -224,6 +293,7 @@ impl Position {
         Self { x, y }
     }
 
+    /// The cells around a cell of the square checkerboard, see [`Lattice::neighbours`].
     pub fn neighbours(&self) -> [Self; 4] {
         [
             Self::new(self.x - 1, self.y - 1),
@@ -233,6 +303,7 @@ impl Position {
         ]
     }
 
+    /// Whether the square checkerboard has a cell here rather than a control rod.
     pub fn is_valid(&self) -> bool {
         (self.x & 1) + (self.y & 1) == 1
     }
@@ -241,38 +312,37 @@ impl Position {
 #[derive(Event)]
 pub struct Cleanup(pub Entity);
 
+/// The spawned core. Its maps are filled in as the cells and control rods of the layout spawn.
 #[derive(Component, Clone, Reflect)]
 #[reflect(Component)]
 pub struct ReactorCore {
-    pub cells: [(Position, Entity); 24],
+    pub layout: ReactorLayout,
     pub cells_by_pos: HashMap<Position, Entity>,
+    pub control_rods_by_pos: HashMap<Position, Entity>,
+    /// The control rod next to a cell, keyed by the position of the cell and of the rod.
     pub edges: HashMap<(Position, Position), Entity>,
-    pub rows: usize,
-    pub columns: usize,
 }
 
-impl Default for ReactorCore {
-    fn default() -> Self {
+impl ReactorCore {
+    pub fn new(layout: ReactorLayout) -> Self {
         Self {
-            cells: [(Position::new(0, 0), Entity::PLACEHOLDER); 24],
+            layout,
             cells_by_pos: HashMap::new(),
+            control_rods_by_pos: HashMap::new(),
             edges: HashMap::new(),
-            rows: 7,
-            columns: 7,
         }
     }
-}
 
-impl ReactorCore {
+    pub fn rows(&self) -> usize {
+        self.layout.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.layout.columns
+    }
+
     pub fn iter_all_positions(&self) -> impl Iterator<Item = Position> + '_ {
-        let column_radius = (self.columns / 2) as i32;
-        let column_rem = (self.columns % 2) as i32;
-        let row_radius = (self.rows / 2) as i32;
-        let row_rem = (self.rows % 2) as i32;
-
-        (-row_radius..row_radius + row_rem).flat_map(move |y| {
-            (-column_radius..column_radius + column_rem).map(move |x| Position::new(x, y))
-        })
+        self.layout.iter_all_positions()
     }
 
     pub fn iter_all_positions_with_cells(
@@ -283,11 +353,11 @@ impl ReactorCore {
     }
 
     pub fn iter_cell_positions(&self) -> impl Iterator<Item = Position> + '_ {
-        self.iter_all_positions().filter(|pos| pos.is_valid())
+        self.layout.iter_cell_positions()
     }
 
     pub fn iter_control_positions(&self) -> impl Iterator<Item = Position> + '_ {
-        self.iter_all_positions().filter(|pos| !pos.is_valid())
+        self.layout.iter_control_positions()
     }
 
     pub fn iter_cells(&self) -> impl Iterator<Item = (Position, Entity)> + '_ {
@@ -295,12 +365,42 @@ impl ReactorCore {
             .filter_map(move |pos| self.cells_by_pos.get(&pos).map(|&entity| (pos, entity)))
     }
 
+    /// Records a spawned cell and links it to the control rods around it.
+    pub fn insert_cell(&mut self, pos: Position, entity: Entity) {
+        self.cells_by_pos.insert(pos, entity);
+        for rod_pos in self.layout.lattice.adjacent(pos) {
+            if let Some(&rod) = self.control_rods_by_pos.get(&rod_pos) {
+                self.edges.insert((pos, rod_pos), rod);
+            }
+        }
+    }
+
+    /// Records a spawned control rod and links it to the cells around it.
+    pub fn insert_control_rod(&mut self, pos: Position, entity: Entity) {
+        self.control_rods_by_pos.insert(pos, entity);
+        for cell_pos in self.layout.lattice.adjacent(pos) {
+            if self.cells_by_pos.contains_key(&cell_pos) {
+                self.edges.insert((cell_pos, pos), entity);
+            }
+        }
+    }
+
     pub fn find_edge(&self, from: Position, to: Position) -> Option<Entity> {
         self.edges
             .get(&(from, to))
             .copied()
             .or_else(|| self.edges.get(&(to, from)).copied())
     }
+
+    /// The position of the slot under `point`, in the core's local coordinates.
+    pub fn position_at(&self, point: Vec2) -> Position {
+        self.layout.lattice.position_at(point)
+    }
+
+    /// Whether `point` lies over a cell or a control rod of the layout.
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.layout.contains(self.position_at(point))
+    }
 }
 
 #[derive(PhysicsLayer, Default)]
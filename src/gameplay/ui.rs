@@ -1,16 +1,19 @@
 use bevy::prelude::*;
-use rand::seq::SliceRandom;
 
 use crate::theme::palette::BUTTON_TEXT;
 
 use super::*;
 
+/// Change of the throttle valve opening per click.
+const THROTTLE_STEP: f32 = 0.05;
+
 pub fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Screen::Gameplay),
         (
             spawn_water_container,
             spawn_steam_container,
+            spawn_turbine_container,
             spawn_power_container,
         ),
     );
@@ -22,6 +25,7 @@ pub fn plugin(app: &mut App) {
             update_steam_score,
             update_power_score,
             update_power_demand,
+            update_turbine,
             update_colors,
         )
             .run_if(in_state(Screen::Gameplay)),
@@ -44,6 +48,14 @@ struct TotalEnergyMarker;
 #[reflect(Component)]
 struct PowerDemandMarker;
 
+#[derive(Component, Clone, Copy, Reflect, Default)]
+#[reflect(Component)]
+struct TurbineStatusMarker;
+
+#[derive(Component, Clone, Copy, Reflect, Default)]
+#[reflect(Component)]
+struct ThrottleMarker;
+
 #[derive(Component, Clone, Copy, Reflect, Default)]
 #[reflect(Component)]
 pub struct ParticleContainerColor(pub Color);
@@ -64,10 +76,10 @@ fn spawn_water_container(
             WaterContainer,
             ParticleContainer {
                 particle: Particle::Water(false),
-                count: 10,
+                count: 0,
             },
             ParticleContainerColor(Color::from(WATER_COLOR)),
-            Transform::from_xyz(-500., -200., 40.),
+            Transform::from_xyz(-500., -245., 40.),
         ))
         .id();
 
@@ -204,7 +216,7 @@ fn spawn_steam_container(
                 count: 0,
             },
             ParticleContainerColor(Color::from(STEAM_COLOR)),
-            Transform::from_xyz(-500., 0., 40.),
+            Transform::from_xyz(-500., -95., 40.),
         ))
         .id();
 
@@ -244,6 +256,123 @@ fn spawn_steam_container(
     ));
 }
 
+fn spawn_turbine_container(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let button_mesh = meshes.add(Rectangle::from_length(26.));
+    let button_material = materials.add(Color::WHITE);
+
+    let root = commands
+        .spawn((
+            Name::new("Turbine container"),
+            StateScoped(Screen::Gameplay),
+            TurbineContainer,
+            ParticleContainerColor(TURBINE_COLOR),
+            Transform::from_xyz(-500., 45., 40.),
+            Visibility::default(),
+        ))
+        .id();
+
+    commands.spawn((
+        Name::new("Title"),
+        ChildOf(root),
+        Anchor::BottomLeft,
+        Text2d::new("turbine"),
+        TextFont {
+            font_size: 36.0,
+            ..default()
+        },
+        TextColor(TURBINE_COLOR),
+        Transform::from_xyz(-100.0, 35.0, 0.0),
+    ));
+
+    let bg = commands
+        .spawn((
+            Name::new("Box"),
+            ChildOf(root),
+            Mesh2d(meshes.add(Rectangle::new(200., 70.))),
+            MeshMaterial2d(materials.add(ColorMaterial::from_color(TURBINE_COLOR))),
+        ))
+        .id();
+
+    commands.spawn((
+        Name::new("Turbine status"),
+        ChildOf(bg),
+        TurbineStatusMarker,
+        Anchor::CenterLeft,
+        Transform::from_xyz(-94., 17., 1.),
+        Text2d::new("load 0%"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+    ));
+    commands.spawn((
+        Name::new("Throttle label"),
+        ChildOf(bg),
+        Anchor::CenterLeft,
+        Transform::from_xyz(-94., -17., 1.),
+        Text2d::new("throttle"),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+    ));
+    commands.spawn((
+        Name::new("Throttle"),
+        ChildOf(bg),
+        ThrottleMarker,
+        Anchor::Center,
+        Transform::from_xyz(45., -17., 1.),
+        Text2d::new("0%"),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+    ));
+    for (name, label, x, step) in [
+        ("Close", "<", 10., -THROTTLE_STEP),
+        ("Open", ">", 80., THROTTLE_STEP),
+    ] {
+        commands
+            .spawn((
+                Name::new(name),
+                ChildOf(bg),
+                Mesh2d(button_mesh.clone()),
+                MeshMaterial2d(button_material.clone()),
+                Transform::from_xyz(x, -17., 1.),
+                Pickable::default(),
+                PlaysClickSound,
+                PlaysHoverSound,
+                children![(
+                    Name::new("Label"),
+                    Text2d::new(label),
+                    TextFont {
+                        font_size: 34.,
+                        ..default()
+                    },
+                    UseBoldFont,
+                    TextColor(TURBINE_COLOR),
+                    Transform::from_xyz(0., 2., 1.),
+                    Pickable::IGNORE,
+                )],
+            ))
+            .observe(
+                move |_: Trigger<Pointer<Click>>, commands: Commands, reactor: Res<Reactor>| {
+                    change_throttle(commands, reactor, step);
+                },
+            );
+    }
+}
+
 fn spawn_power_container(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -254,7 +383,6 @@ fn spawn_power_container(
             Name::new("Power container"),
             StateScoped(Screen::Gameplay),
             EnergyContainer,
-            TicksWithoutPower::default(),
             ParticleContainer {
                 particle: Particle::Energy,
                 count: 0,
@@ -365,6 +493,32 @@ fn update_power_demand(
     text.0 = format_power(demand.0 as u64); // TODO: change the demand.0 type
 }
 
+/// Shows how much of the load the turbine carries and how efficiently, or how long until it
+/// restarts after a trip.
+fn update_turbine(
+    mut query: Query<
+        (&Turbine, &mut ParticleContainerColor),
+        (With<TurbineContainer>, Changed<Turbine>),
+    >,
+    mut status: Single<&mut Text2d, With<TurbineStatusMarker>>,
+    mut throttle: Single<&mut Text2d, (With<ThrottleMarker>, Without<TurbineStatusMarker>)>,
+) {
+    for (turbine, mut color) in &mut query {
+        if turbine.tripped > 0 {
+            status.0 = format!("tripped {}", turbine.tripped);
+            color.0 = WARNING_COLOR.into();
+        } else {
+            status.0 = format!(
+                "load {:.0}% eff {:.0}%",
+                turbine.load_ratio * 100.,
+                turbine.efficiency * 100.
+            );
+            color.0 = TURBINE_COLOR;
+        }
+        throttle.0 = format!("{:.0}%", turbine.throttle * 100.);
+    }
+}
+
 fn format_power(power: u64) -> String {
     if power > 10_000_000_000 {
         format!("{} B", power / 1_000_000_000)
@@ -396,24 +550,21 @@ fn update_colors(
     }
 }
 
-fn on_click_water_control_decrease(_: Trigger<Pointer<Click>>, query: Single<&mut WaterFlow>) {
-    query.into_inner().decrease();
+fn on_click_water_control_decrease(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(PlayerAction::DecreaseWaterFlow);
 }
 
-fn on_click_water_control_increase(_: Trigger<Pointer<Click>>, query: Single<&mut WaterFlow>) {
-    query.into_inner().increase();
+fn on_click_water_control_increase(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(PlayerAction::IncreaseWaterFlow);
 }
 
-fn on_click_water_distribute(
-    _: Trigger<Pointer<Click>>,
-    mut commands: Commands,
-    query: Query<Entity, With<Cell>>,
-) {
-    let mut rng = rand::rng();
-    let mut cells: Vec<_> = query.into_iter().collect();
-    cells.shuffle(&mut rng);
+fn change_throttle(mut commands: Commands, reactor: Res<Reactor>, step: f32) {
+    let throttle = (reactor.turbine.throttle + step).clamp(0., 1.);
+    commands.trigger(PlayerAction::SetThrottle {
+        percent: (throttle * 100.).round() as u8,
+    });
+}
 
-    for cell in cells {
-        commands.trigger_targets(FlowWaterParticlesIntoCell, cell);
-    }
+fn on_click_water_distribute(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(PlayerAction::DistributeWater);
 }
@@ -0,0 +1,199 @@
+//! Runs the reactor simulation without a window, rendering or audio.
+//!
+//! Used for balancing: the gameplay plugins are driven with a fixed frame time, so thousands
+//! of simulation ticks can be run per second.
+
+use std::time::Duration;
+
+use avian2d::PhysicsPlugins;
+use bevy::{prelude::*, scene::ScenePlugin, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{
+    asset_tracking,
+    gameplay::{
+        self, Balance, Difficulty, NextReplay, NextRunSeed, Operator, Reactor, ReactorController,
+        ReactorLayout, Recording, ReplayFile, Simulation, SimulationRng,
+    },
+    pause_plugin,
+    screens::{Screen, game_over::GameOver},
+};
+
+pub use crate::screens::game_over::GameOverCause;
+
+pub struct HeadlessConfig {
+    /// Number of simulation ticks to run before stopping.
+    pub ticks: u64,
+    /// Seed for the run. A random seed is picked when this is `None`.
+    pub seed: Option<u64>,
+    /// Amount of virtual time that passes in every frame.
+    pub frame_time: Duration,
+    /// Player input to play back. Its seed takes precedence over [`HeadlessConfig::seed`].
+    pub replay: Option<ReplayFile>,
+    /// Tuning values for the run. The balance asset isn't loaded in headless runs.
+    pub balance: Balance,
+    /// Difficulty of the run. A replay's difficulty takes precedence.
+    pub difficulty: Difficulty,
+    /// Layout of the core. A replay's layout takes precedence.
+    pub layout: ReactorLayout,
+    /// The controller that operates the reactor, plugged in as the [`Operator`]. Its input is
+    /// ignored during a replay.
+    pub controller: Option<Box<dyn ReactorController>>,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            ticks: 1000,
+            seed: None,
+            frame_time: Duration::from_millis(50),
+            replay: None,
+            balance: Balance::default(),
+            difficulty: Difficulty::default(),
+            layout: ReactorLayout::default(),
+            controller: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HeadlessReport {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub ticks: u64,
+    pub power_generated: usize,
+    pub power_demand: usize,
+    /// Why the run ended, or `None` if it survived all ticks.
+    pub cause: Option<GameOverCause>,
+    /// The player input of the run, including any replayed input.
+    pub recording: ReplayFile,
+}
+
+/// Builds an app that runs the gameplay plugins without rendering or audio.
+pub fn headless_app(config: HeadlessConfig) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        AssetPlugin::default(),
+        TransformPlugin,
+        // The physics plugins look for the scene spawner when they build colliders.
+        ScenePlugin,
+        PhysicsPlugins::default(),
+    ));
+    // Registered by the render plugins in the full app.
+    app.init_asset::<Mesh>();
+    app.init_asset::<ColorMaterial>();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(config.frame_time));
+
+    app.insert_state(Screen::Gameplay);
+    app.add_plugins((asset_tracking::plugin, pause_plugin, gameplay::core_plugin));
+    if let Some(seed) = config.seed {
+        app.insert_resource(NextRunSeed(Some(seed)));
+    }
+    app.insert_resource(NextReplay(config.replay));
+    app.insert_resource(config.balance);
+    app.insert_resource(config.difficulty);
+    app.insert_resource(config.layout);
+    app.insert_resource(Operator(config.controller));
+
+    app.init_resource::<HeadlessOutcome>();
+    app.add_observer(record_game_over);
+
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Runs a single game until it ends or the configured number of ticks has passed.
+pub fn run_headless(config: HeadlessConfig) -> HeadlessReport {
+    let max_ticks = config.ticks;
+    let mut app = headless_app(config);
+    run_until(&mut app, max_ticks);
+    report(app.world())
+}
+
+fn run_until(app: &mut App, max_ticks: u64) {
+    loop {
+        app.update();
+
+        let world = app.world();
+        let ticks = world.resource::<Time<Simulation>>().context().ticks();
+        if ticks >= max_ticks || world.resource::<HeadlessOutcome>().0.is_some() {
+            break;
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct HeadlessOutcome(Option<GameOver>);
+
+fn record_game_over(trigger: Trigger<GameOver>, mut outcome: ResMut<HeadlessOutcome>) {
+    if outcome.0.is_none() {
+        outcome.0 = Some(*trigger.event());
+    }
+}
+
+fn report(world: &World) -> HeadlessReport {
+    let reactor = world.resource::<Reactor>();
+    HeadlessReport {
+        seed: world.resource::<SimulationRng>().seed(),
+        difficulty: reactor.difficulty,
+        ticks: world.resource::<Time<Simulation>>().context().ticks(),
+        power_generated: reactor.energy,
+        power_demand: reactor.demand.demand,
+        cause: reactor.failure,
+        recording: world.resource::<Recording>().0.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::Autopilot;
+
+    /// Runs `config` and returns the reactor in a form that can be compared, and the recording.
+    fn run(config: HeadlessConfig) -> (String, ReplayFile) {
+        let max_ticks = config.ticks;
+        let mut app = headless_app(config);
+        run_until(&mut app, max_ticks);
+        let world = app.world();
+        (
+            ron::to_string(&world.resource::<Reactor>().0).unwrap(),
+            world.resource::<Recording>().0.clone(),
+        )
+    }
+
+    fn operated(seed: u64) -> HeadlessConfig {
+        HeadlessConfig {
+            ticks: 120,
+            seed: Some(seed),
+            controller: Some(Box::new(Autopilot::switched_on())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_and_frames_give_the_same_run() {
+        let (first, _) = run(operated(7));
+        let (second, _) = run(operated(7));
+        assert_eq!(first, second);
+
+        let (other, _) = run(operated(8));
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn replay_reproduces_the_run() {
+        let (played, recording) = run(operated(7));
+        assert!(!recording.actions.is_empty());
+
+        // The replay runs on the recorded frames, not on its own.
+        let (replayed, _) = run(HeadlessConfig {
+            ticks: 120,
+            frame_time: Duration::from_millis(17),
+            replay: Some(recording),
+            ..Default::default()
+        });
+        assert_eq!(played, replayed);
+    }
+}
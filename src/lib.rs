@@ -8,14 +8,27 @@ mod audio;
 #[cfg(feature = "dev")]
 mod dev_tools;
 pub mod gameplay;
+pub mod headless;
 mod menus;
 mod screens;
 mod theme;
 
 use avian2d::PhysicsPlugins;
 use bevy::{asset::AssetMetaCheck, prelude::*};
-use gameplay::schedule::RunSimulation;
+use gameplay::schedule::{RunSimulation, simulation_stepping};
 
+/// The game with its window, menus and audio.
+///
+/// A [`ReactorController`](gameplay::ReactorController) can operate the reactor in every run by
+/// plugging it in as the [`Operator`](gameplay::Operator) after this plugin, the same way
+/// [`HeadlessConfig::controller`](headless::HeadlessConfig::controller) does for headless runs:
+///
+/// ```ignore
+/// App::new()
+///     .add_plugins(AppPlugin)
+///     .insert_resource(Operator(Some(Box::new(Autopilot::switched_on()))))
+///     .run();
+/// ```
 pub struct AppPlugin;
 
 impl Plugin for AppPlugin {
@@ -67,15 +80,19 @@ impl Plugin for AppPlugin {
         );
 
         // Set up the `Pause` state.
-        app.init_state::<Pause>();
-        app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
-        app.configure_sets(
-            RunSimulation,
-            PausableSystems.run_if(in_state(Pause(false))),
-        );
+        app.add_plugins(pause_plugin);
     }
 }
 
+fn pause_plugin(app: &mut App) {
+    app.init_state::<Pause>();
+    app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+    app.configure_sets(
+        RunSimulation,
+        PausableSystems.run_if(in_state(Pause(false)).or(simulation_stepping)),
+    );
+}
+
 /// High-level groupings of systems for the app in the `Update` schedule.
 /// When adding a new variant, make sure to order it in the `configure_sets`
 /// call above.
@@ -0,0 +1,57 @@
+//! The difficulty menu, opened before starting a new run.
+
+use bevy::{ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    asset_tracking::ResourceHandles, gameplay::Difficulty, menus::Menu, screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Difficulty), spawn_difficulty_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Difficulty).and(input_just_pressed(KeyCode::Escape))),
+    );
+}
+
+fn spawn_difficulty_menu(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Difficulty Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::Difficulty),
+        Children::spawn((
+            Spawn(widget::header("difficulty")),
+            SpawnIter(Difficulty::ALL.into_iter().map(difficulty_button)),
+            Spawn(widget::label(
+                "custom plays with the values in reactor.balance.ron",
+            )),
+            Spawn(widget::button("Back", go_back_on_click)),
+        )),
+    ));
+}
+
+fn difficulty_button(difficulty: Difficulty) -> impl Bundle {
+    widget::button(
+        difficulty.label(),
+        move |_: Trigger<Pointer<Click>>,
+              mut selected: ResMut<Difficulty>,
+              resource_handles: Res<ResourceHandles>,
+              mut next_screen: ResMut<NextState<Screen>>| {
+            *selected = difficulty;
+            if resource_handles.is_all_done() {
+                next_screen.set(Screen::Gameplay);
+            } else {
+                next_screen.set(Screen::Loading);
+            }
+        },
+    )
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
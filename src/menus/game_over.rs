@@ -1,8 +1,9 @@
 use bevy::{ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*};
 
 use crate::{
+    gameplay::{NextReplay, NextRunSeed, Recording, SimulationRng},
     screens::{
-        Screen,
+        EditorLayout, Screen,
         game_over::{GameOver, GameOverCause},
     },
     theme::widget,
@@ -18,7 +19,7 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-fn spawn_menu(mut commands: Commands, game_over: Single<&GameOver>) {
+fn spawn_menu(mut commands: Commands, game_over: Single<&GameOver>, rng: Res<SimulationRng>) {
     commands.spawn((
         widget::ui_root("Game Over Menu"),
         GlobalZIndex(2),
@@ -30,6 +31,13 @@ fn spawn_menu(mut commands: Commands, game_over: Single<&GameOver>) {
                 "you've generated enough electricity to power"
             ]),
             widget::header(text_from_power(game_over.power_generated)),
+            widget::label(format!(
+                "difficulty: {}   seed: {}",
+                game_over.difficulty.label(),
+                rng.seed()
+            )),
+            widget::button("play seed again", replay_seed),
+            widget::button("watch replay", watch_replay),
             widget::button("Back", go_back_on_click),
         ],
     ));
@@ -53,8 +61,37 @@ fn grid(content: Vec<&'static str>) -> impl Bundle {
     )
 }
 
-fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Title);
+/// Goes back to the title screen, or to the editor after a test run.
+fn go_back_on_click(
+    _: Trigger<Pointer<Click>>,
+    editing: Option<Res<EditorLayout>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_screen.set(if editing.is_some() {
+        Screen::Editor
+    } else {
+        Screen::Title
+    });
+}
+
+fn replay_seed(
+    _: Trigger<Pointer<Click>>,
+    rng: Res<SimulationRng>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_seed.0 = Some(rng.seed());
+    next_screen.set(Screen::Gameplay);
+}
+
+fn watch_replay(
+    _: Trigger<Pointer<Click>>,
+    recording: Res<Recording>,
+    mut next_replay: ResMut<NextReplay>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_replay.0 = Some(recording.0.clone());
+    next_screen.set(Screen::Gameplay);
 }
 
 fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
@@ -68,6 +105,7 @@ fn text_from_cause(cause: GameOverCause) -> &'static str {
         GameOverCause::Explosion => {
             "boom! that's the sound your reactor made when it reached its pressure limits"
         }
+        GameOverCause::Overheating => "the fuel got so hot it melted through the reactor floor",
     }
 }
 
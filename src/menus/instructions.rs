@@ -8,6 +8,7 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::HowToPlay), spawn_menu);
     app.add_systems(OnEnter(Menu::HowToPlay2), spawn_menu2);
     app.add_systems(OnEnter(Menu::HowToPlay3), spawn_menu3);
+    app.add_systems(OnEnter(Menu::HowToPlay4), spawn_menu4);
 }
 
 fn spawn_menu(mut commands: Commands) {
@@ -29,8 +30,9 @@ fn spawn_menu(mut commands: Commands) {
                 widget::header("how to play"),
                 widget::text("Welcome! You've been chosen to operate our nuclear reactor, hopefully you know how. What? You don't? Don't worry, it's not rocket science!"),
                 widget::text("In the center, you'll see the layout of the reactor made of circular cells and square control rods."),
-                widget::text("Cells contain fuel - either uranium or xenon. Uranium spontaneously releases neutrons and splits into even more if hit by one. Xenon doesn't do anything. That is, until woken up by a neutron!"),
-                widget::text("Control rods can be either inserted (100%) or not (0%). Click on them to toggle their insertion. When inserted, they absorb all neutrons."),
+                widget::text("Cells contain fuel - either uranium or xenon. Uranium spontaneously releases neutrons and splits into even more if hit by one. Xenon doesn't do anything. That is, until enough neutrons wake it up!"),
+                widget::text("Control rods can be inserted anywhere from 0% to 100%. Click on them to move them all the way in or out, or drag them up and down or scroll over them to pick the exact insertion they move to. The further in, the more neutrons they absorb."),
+                widget::text("To move several rods at once, shift-click them or hold shift and drag a box around them. The arrow keys move the selected rods by 10%, page up and page down push them all the way in or out. Press B or click the button in the top right corner to switch the legend for the bank panel, where \"new bank\" keeps the selection as a bank of the layout that you can select and move again later. Banks are saved with the layout."),
                 widget::button("next", second_page),
             ]
         )],
@@ -57,6 +59,8 @@ fn spawn_menu2(mut commands: Commands) {
                 widget::text("Neutrons flying around would be useless without something to interact with. That something is water."),
                 widget::text("When hit by a neutron, a water particle splits into steam particles that can then be turned into energy by our turbines. Don't starve them, we've got people that rely us so we need steady supply of steam!"),
                 widget::text("You can add water by clicking on individual cells or by using the 'distribtue' button in the water section on the left. There, you can also control how many particles will be added each time."),
+                widget::text("Every cell also has a coolant pump. Scroll over a cell to turn its pump up or down and it moves that much water into the cell every tick on its own. Pumps run on the power you generate, and they trip and stop for a while when there isn't enough of it or their cell is under too much pressure."),
+                widget::text("Steam reaches the power grid through the turbine, between the steam and power sections. The throttle valve decides how much of the stored steam goes in, and the turbine only slowly speeds up or slows down to match it. It works best at 100% load, when it spins with just the steam the grid and the pumps ask for, and it never makes more power than the steam let in, leaving what it doesn't need stored. Let in too much steam and it races past its limit, let it run out and it stalls. Either way it trips and needs a few ticks to restart, without power in the meantime."),
                 widget::button("next", third_page),
             ]
         )],
@@ -81,9 +85,41 @@ fn spawn_menu3(mut commands: Commands) {
             children![
                 widget::header("how to play"),
                 widget::text("Now you know the basics, now for some final tips."),
-                widget::text("You might think adding a lot of water is a great idea to ramp up steam production. Be careful! Water absorbs neutrons and prevents them from forming chain reactions! Too much of it may also lead to too high pressure."),
-                widget::text("Uranium has a small chance to turn into xenon. Without sufficient reactivity, you're going to end up with too much of dead fuel cells and restarting the reaction might prove difficult."),
+                widget::text("Neutrons leave uranium fast and rarely split it. Water slows them down so uranium catches them easily, but slow neutrons are also absorbed by the water they hit next."),
+                widget::text("You might think adding a lot of water is a great idea to ramp up steam production. Be careful! Too much water absorbs the neutrons before they can form chain reactions, and it may also lead to too high pressure."),
+                widget::text("Every split heats up its cell and the heat spreads to the cells around it. Hot cells boil their water on their own, but a cell that runs dry keeps getting hotter until it melts."),
+                widget::text("Splitting uranium leaves iodine behind, which slowly turns into xenon. Neutrons burn xenon away, but if you slow the reaction down the xenon keeps piling up and your fuel cells go dead. Restarting the reaction from there might prove difficult."),
+                widget::text("Uranium burns up as it splits and reacts less and less, until it's spent and only swallows neutrons. Right click a cell with spent fuel to replace it, the cell stays dark while the new rod goes in."),
+                widget::text("Custom layouts can use other fuels. Plutonium releases more neutrons per split, thorium doesn't split at all but turns into uranium after catching enough neutrons, and MOX sits somewhere in between."),
+                widget::text("Not every neutron from a split leaves right away. Some linger in the fuel and trickle out over the next few ticks, so a reaction keeps going for a moment after you push the control rods in."),
                 widget::text("Control the reactions by balancing insertion levels of control rods and the water amount in cells."),
+                widget::button("next", fourth_page),
+            ]
+        )],
+    ));
+}
+
+fn spawn_menu4(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Instructions Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::HowToPlay4),
+        children![(
+            Node {
+                max_width: Val::Px(900.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::new(Val::Px(50.), Val::Px(50.), Val::Px(50.), Val::Px(50.),),
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            BackgroundColor(BUTTON_TEXT),
+            children![
+                widget::header("how to play"),
+                widget::text("Keys 1-4 change the simulation speed. Space pauses the reactor and N then advances it by a single phase."),
+                widget::text("In an emergency, hit SCRAM or press X. Every control rod slams all the way in and stays there until you reset it, which takes a while and leaves the grid asking for extra power to make up for the outage. Press R or click reset once it's ready."),
+                widget::text("The autopilot, the next panel after the banks, moves its rods to keep up with the power demand on its own. Tune its gains and hand it the selected rods from the panel, but keep an eye on it: it can't do more than pull its rods all the way out, and every now and then it breaks down and needs a while to be repaired."),
+                widget::text("If you'd rather automate things yourself, the script panel runs an operator script written in Rhai after every tick. Click the code to type into it and move around with the arrow keys, home, end and page up or down, or scroll over it to read the rest. Press escape when you're done and reload to run the new version. Scripts that fail or take too long to run are stopped."),
                 widget::text("Good luck!"),
                 widget::button("start", close_menu),
             ]
@@ -99,6 +135,10 @@ fn third_page(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>
     next_menu.set(Menu::HowToPlay3);
 }
 
+fn fourth_page(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::HowToPlay4);
+}
+
 fn close_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::None);
 }
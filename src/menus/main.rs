@@ -2,40 +2,67 @@
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, menus::Menu, screens::Screen, theme::widget};
+use crate::{
+    asset_tracking::ResourceHandles,
+    gameplay::{ResumeRun, has_saved_run, load_saved_run},
+    menus::Menu,
+    screens::Screen,
+    theme::widget,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Main), spawn_main_menu);
 }
 
 fn spawn_main_menu(mut commands: Commands) {
-    commands.spawn((
-        widget::ui_root("Main Menu"),
-        GlobalZIndex(2),
-        StateScoped(Menu::Main),
-        #[cfg(not(target_family = "wasm"))]
-        children![
-            widget::title("mini reactor"),
-            widget::button("play", enter_loading_or_gameplay_screen),
-            widget::button("settings", open_settings_menu),
-            widget::button("credits", open_credits_menu),
-            widget::button("exit", exit_app),
-        ],
-        #[cfg(target_family = "wasm")]
-        children![
-            widget::title("mini reactor"),
-            widget::button("play", enter_loading_or_gameplay_screen),
-            widget::button("settings", open_settings_menu),
-            widget::button("credits", open_credits_menu),
-        ],
-    ));
-}
-
-fn enter_loading_or_gameplay_screen(
+    let root = commands
+        .spawn((
+            widget::ui_root("Main Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::Main),
+            #[cfg(not(target_family = "wasm"))]
+            children![
+                widget::title("mini reactor"),
+                widget::button("play", open_difficulty_menu),
+                widget::button("editor", enter_editor_screen),
+                widget::button("settings", open_settings_menu),
+                widget::button("credits", open_credits_menu),
+                widget::button("exit", exit_app),
+            ],
+            #[cfg(target_family = "wasm")]
+            children![
+                widget::title("mini reactor"),
+                widget::button("play", open_difficulty_menu),
+                widget::button("editor", enter_editor_screen),
+                widget::button("settings", open_settings_menu),
+                widget::button("credits", open_credits_menu),
+            ],
+        ))
+        .id();
+
+    if has_saved_run() {
+        let button = commands
+            .spawn(widget::button("continue", continue_saved_run))
+            .id();
+        commands.entity(root).insert_children(1, &[button]);
+    }
+}
+
+fn open_difficulty_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Difficulty);
+}
+
+fn continue_saved_run(
     _: Trigger<Pointer<Click>>,
+    mut resume: ResMut<ResumeRun>,
     resource_handles: Res<ResourceHandles>,
     mut next_screen: ResMut<NextState<Screen>>,
 ) {
+    if let Err(err) = load_saved_run(&mut resume) {
+        warn!("failed to load saved run: {err}");
+        return;
+    }
+
     if resource_handles.is_all_done() {
         next_screen.set(Screen::Gameplay);
     } else {
@@ -43,6 +70,10 @@ fn enter_loading_or_gameplay_screen(
     }
 }
 
+fn enter_editor_screen(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Editor);
+}
+
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
@@ -1,6 +1,7 @@
 //! The game's menus and transitions between them.
 
 mod credits;
+mod difficulty;
 mod game_over;
 mod instructions;
 mod main;
@@ -14,6 +15,7 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins((
         credits::plugin,
+        difficulty::plugin,
         main::plugin,
         settings::plugin,
         pause::plugin,
@@ -29,10 +31,12 @@ pub enum Menu {
     None,
     Main,
     Credits,
+    Difficulty,
     Settings,
     Pause,
     GameOver,
     HowToPlay,
     HowToPlay2,
     HowToPlay3,
+    HowToPlay4,
 }
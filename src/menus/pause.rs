@@ -3,7 +3,7 @@
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
 use crate::{
-    gameplay::{EnergyContainer, ParticleContainer},
+    gameplay::{EnergyContainer, ParticleContainer, Reactor},
     menus::Menu,
     screens::game_over::{GameOver, GameOverCause},
     theme::widget,
@@ -22,6 +22,15 @@ fn spawn_pause_menu(mut commands: Commands) {
         widget::ui_root("Pause Menu"),
         GlobalZIndex(2),
         StateScoped(Menu::Pause),
+        #[cfg(not(target_family = "wasm"))]
+        children![
+            widget::header("game paused"),
+            widget::button("continue", close_menu),
+            widget::button("settings", open_settings_menu),
+            widget::button("save and quit", save_and_quit),
+            widget::button("abandon game", quit_to_title),
+        ],
+        #[cfg(target_family = "wasm")]
         children![
             widget::header("game paused"),
             widget::button("continue", close_menu),
@@ -39,9 +48,20 @@ fn close_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>
     next_menu.set(Menu::None);
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn save_and_quit(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<crate::screens::Screen>>,
+) {
+    commands.trigger(crate::gameplay::SaveRun);
+    next_screen.set(crate::screens::Screen::Title);
+}
+
 fn quit_to_title(
     _: Trigger<Pointer<Click>>,
     mut commands: Commands,
+    reactor: Res<Reactor>,
     query: Query<&ParticleContainer, With<EnergyContainer>>,
 ) {
     let mut power_generated = 0;
@@ -51,6 +71,7 @@ fn quit_to_title(
     commands.trigger(GameOver {
         cause: GameOverCause::PlayerAbandoned,
         power_generated,
+        difficulty: reactor.difficulty,
     });
 }
 
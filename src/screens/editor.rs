@@ -0,0 +1,561 @@
+//! A screen for designing reactor layouts.
+//!
+//! Slots are painted with the selected [`Brush`], by clicking them or dragging over them. The
+//! layout can be test-run right away and saved to `saves/reactor.layout.ron`, which new runs
+//! start from instead of the shipped layout.
+
+use std::collections::HashMap;
+
+use bevy::{prelude::*, text::TextBounds};
+
+use crate::{
+    asset_tracking::ResourceHandles,
+    gameplay::{
+        Balance, CELL_COLOR, CELL_OUTER_SIZE, CELL_RADIUS, CONTROL_ROD_COLOR_INSERTED,
+        CONTROL_ROD_RADIUS, CrtSettings, FUEL_ROD_RADIUS, FuelRod, Lattice, LayoutAssets, Position,
+        ReactorLayout, Slot, URANIUM_COLOR,
+    },
+    screens::Screen,
+    theme::{
+        interaction::{PlaysClickSound, PlaysHoverSound, UseBoldFont},
+        palette::BUTTON_TEXT,
+    },
+};
+
+/// The largest number of rows or columns a layout can have in the editor.
+const MAX_LAYOUT_SIZE: usize = 15;
+/// The width and height the grid is shrunk to fit into.
+const GRID_SIZE: f32 = 660.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Brush>();
+    app.init_resource::<Brush>();
+
+    app.add_systems(
+        OnEnter(Screen::Editor),
+        (start_editing, spawn_editor, spawn_grid).chain(),
+    );
+    app.add_systems(
+        OnEnter(Screen::Title),
+        stop_editing.run_if(resource_exists::<EditorLayout>),
+    );
+    app.add_systems(
+        Update,
+        (
+            spawn_grid.run_if(resource_changed::<EditorLayout>),
+            update_size_label.run_if(resource_changed::<EditorLayout>),
+            update_brush_buttons.run_if(resource_changed::<Brush>),
+        )
+            .run_if(in_state(Screen::Editor)),
+    );
+}
+
+/// The layout being edited. It outlives test runs, so they come back to the editor.
+#[derive(Resource, Clone, Debug, Deref, DerefMut)]
+pub struct EditorLayout(pub ReactorLayout);
+
+/// What clicking a slot puts there.
+#[derive(Resource, Clone, Copy, Default, Debug, Eq, PartialEq, Reflect)]
+#[reflect(Resource)]
+enum Brush {
+    Empty,
+    #[default]
+    Cell,
+    ControlRod,
+    Fuel(FuelRod),
+}
+
+impl Brush {
+    const ALL: [Self; 8] = [
+        Self::Empty,
+        Self::Cell,
+        Self::ControlRod,
+        Self::Fuel(FuelRod::Uranium),
+        Self::Fuel(FuelRod::Plutonium),
+        Self::Fuel(FuelRod::Thorium),
+        Self::Fuel(FuelRod::Mox),
+        Self::Fuel(FuelRod::Xenon),
+    ];
+
+    fn slot(&self) -> Slot {
+        match self {
+            Self::Empty => Slot::Empty,
+            Self::Cell => Slot::Cell(None),
+            Self::ControlRod => Slot::ControlRod,
+            Self::Fuel(fuel) => Slot::Cell(Some(*fuel)),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Cell => "cell",
+            Self::ControlRod => "rod",
+            Self::Fuel(fuel) => fuel.label(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct EditorGrid;
+
+#[derive(Component)]
+struct EditorSlot(Position);
+
+#[derive(Component)]
+struct BrushButton(Brush);
+
+#[derive(Component)]
+struct SizeLabel;
+
+#[derive(Component)]
+struct StatusLabel;
+
+fn start_editing(
+    mut commands: Commands,
+    editing: Option<Res<EditorLayout>>,
+    layout: Res<ReactorLayout>,
+) {
+    if editing.is_none() {
+        commands.insert_resource(EditorLayout(layout.clone()));
+    }
+}
+
+/// Puts the saved layout back in place of the one that was last test-run.
+fn stop_editing(
+    mut commands: Commands,
+    layout_assets: Option<Res<LayoutAssets>>,
+    assets: Res<Assets<ReactorLayout>>,
+    mut layout: ResMut<ReactorLayout>,
+) {
+    commands.remove_resource::<EditorLayout>();
+    if let Some(saved) = saved_or_shipped(layout_assets.as_deref(), &assets) {
+        layout.set_if_neq(saved);
+    }
+}
+
+/// The layout saved from the editor, or the one shipped with the game if none was saved.
+fn saved_or_shipped(
+    layout_assets: Option<&LayoutAssets>,
+    assets: &Assets<ReactorLayout>,
+) -> Option<ReactorLayout> {
+    ReactorLayout::load_saved().or_else(|| {
+        layout_assets
+            .and_then(|handles| assets.get(&handles.layout))
+            .cloned()
+    })
+}
+
+fn spawn_editor(
+    mut commands: Commands,
+    brush: Res<Brush>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        Name::new("Camera"),
+        Camera2d,
+        StateScoped(Screen::Editor),
+        CrtSettings::default(),
+    ));
+    commands.spawn((
+        Name::new("Grid"),
+        EditorGrid,
+        StateScoped(Screen::Editor),
+        Transform::from_xyz(100., 0., 0.),
+        Visibility::default(),
+    ));
+
+    let button_mesh = meshes.add(Rectangle::new(120., 34.));
+    let small_button_mesh = meshes.add(Rectangle::new(34., 34.));
+    let root = commands
+        .spawn((
+            Name::new("Editor controls"),
+            StateScoped(Screen::Editor),
+            Transform::from_xyz(-480., 0., 40.),
+            Visibility::default(),
+        ))
+        .id();
+
+    commands.spawn((
+        ChildOf(root),
+        label("brush"),
+        Transform::from_xyz(0., 320., 1.),
+    ));
+    for (i, option) in Brush::ALL.into_iter().enumerate() {
+        let (column, row) = (i % 2, i / 2);
+        commands
+            .spawn((
+                button(option.label()),
+                ChildOf(root),
+                Mesh2d(button_mesh.clone()),
+                BrushButton(option),
+                MeshMaterial2d(materials.add(brush_button_color(option, *brush))),
+                Transform::from_xyz(column as f32 * 130. - 65., 280. - row as f32 * 42., 1.),
+            ))
+            .observe(on_click_brush);
+    }
+
+    commands.spawn((
+        ChildOf(root),
+        SizeLabel,
+        label(""),
+        Transform::from_xyz(0., 60., 1.),
+    ));
+    let white = materials.add(Color::WHITE);
+    let resize_buttons: [(&str, f32, (isize, isize)); 4] = [
+        ("-", -90., (-1, 0)),
+        ("+", -50., (1, 0)),
+        ("-", 50., (0, -1)),
+        ("+", 90., (0, 1)),
+    ];
+    for (text, x, (rows, columns)) in resize_buttons {
+        commands
+            .spawn((
+                button(text),
+                ChildOf(root),
+                Mesh2d(small_button_mesh.clone()),
+                MeshMaterial2d(white.clone()),
+                Transform::from_xyz(x, 20., 1.),
+            ))
+            .observe(
+                move |_: Trigger<Pointer<Click>>, mut layout: ResMut<EditorLayout>| {
+                    let rows = layout.rows.saturating_add_signed(rows);
+                    let columns = layout.columns.saturating_add_signed(columns);
+                    if (1..=MAX_LAYOUT_SIZE).contains(&rows)
+                        && (1..=MAX_LAYOUT_SIZE).contains(&columns)
+                    {
+                        layout.0 = layout.resized(rows, columns);
+                    }
+                },
+            );
+    }
+    commands.spawn((
+        ChildOf(root),
+        label("rows"),
+        Transform::from_xyz(-70., -10., 1.),
+    ));
+    commands.spawn((
+        ChildOf(root),
+        label("columns"),
+        Transform::from_xyz(70., -10., 1.),
+    ));
+
+    let action = (
+        ChildOf(root),
+        Mesh2d(button_mesh.clone()),
+        MeshMaterial2d(white.clone()),
+    );
+    let mut y = -50.;
+    let mut next_y = || {
+        let current = y;
+        y -= 42.;
+        Transform::from_xyz(0., current, 1.)
+    };
+    commands
+        .spawn((button("classic"), action.clone(), next_y()))
+        .observe(on_click_classic);
+    commands
+        .spawn((button("lattice"), action.clone(), next_y()))
+        .observe(on_click_lattice);
+    commands
+        .spawn((button("test run"), action.clone(), next_y()))
+        .observe(on_click_test_run);
+    #[cfg(not(target_family = "wasm"))]
+    {
+        commands
+            .spawn((button("save"), action.clone(), next_y()))
+            .observe(on_click_save);
+        commands
+            .spawn((button("load"), action.clone(), next_y()))
+            .observe(on_click_load);
+    }
+    commands
+        .spawn((button("back"), action, next_y()))
+        .observe(on_click_back);
+
+    commands.spawn((
+        ChildOf(root),
+        StatusLabel,
+        label(""),
+        TextBounds::new_horizontal(240.),
+        Transform::from_xyz(0., -320., 1.),
+    ));
+}
+
+fn button(text: &'static str) -> impl Bundle {
+    (
+        Name::new(text),
+        Pickable::default(),
+        PlaysClickSound,
+        PlaysHoverSound,
+        children![button_label(text)],
+    )
+}
+
+fn button_label(text: &'static str) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text2d::new(text),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(BUTTON_TEXT),
+        Transform::from_xyz(0., 0., 1.),
+        Pickable::IGNORE,
+    )
+}
+
+fn label(text: &'static str) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text2d::new(text),
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        UseBoldFont,
+        TextColor(Color::WHITE),
+        Pickable::IGNORE,
+    )
+}
+
+fn brush_button_color(option: Brush, current: Brush) -> Color {
+    if option == current {
+        URANIUM_COLOR
+    } else {
+        Color::WHITE
+    }
+}
+
+/// Respawns the slots of the grid to match the edited layout.
+fn spawn_grid(
+    mut commands: Commands,
+    layout: Res<EditorLayout>,
+    balance: Res<Balance>,
+    grid: Single<(Entity, &mut Transform), With<EditorGrid>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let (grid, mut transform) = grid.into_inner();
+    commands.entity(grid).despawn_related::<Children>();
+
+    let extent = layout
+        .iter_all_positions()
+        .map(|pos| layout.translation(pos).abs().max_element())
+        .fold(0., f32::max);
+    let size = extent * 2. + layout.lattice.pitch();
+    transform.scale = Vec3::splat((GRID_SIZE / size).min(1.));
+
+    let slot_mesh = match layout.lattice {
+        Lattice::Square => meshes.add(Rectangle::from_length(CELL_OUTER_SIZE - 4.)),
+        Lattice::Hex => meshes.add(RegularPolygon::new(
+            layout.lattice.pitch() / 3f32.sqrt() - 2.,
+            6,
+        )),
+    };
+    let slot_material = materials.add(Color::srgba(1., 1., 1., 0.05));
+    let cell_mesh = meshes.add(Circle::new(CELL_RADIUS));
+    let cell_material = materials.add(Color::from(CELL_COLOR));
+    let fuel_mesh = meshes.add(Circle::new(FUEL_ROD_RADIUS));
+    let random_fuel_material = materials.add(Color::srgb(0.4, 0.4, 0.4));
+    let fuel_materials: HashMap<_, _> = FuelRod::ALL
+        .into_iter()
+        .map(|fuel| (fuel, materials.add(balance.fuels.color(fuel))))
+        .collect();
+    let rod_mesh = meshes.add(Rectangle::from_length(CONTROL_ROD_RADIUS * 2.));
+    let rod_material = materials.add(CONTROL_ROD_COLOR_INSERTED);
+
+    for (pos, slot) in layout.iter_slots() {
+        let entity = commands
+            .spawn((
+                Name::new(format!("Slot {}/{}", pos.x, pos.y)),
+                ChildOf(grid),
+                EditorSlot(pos),
+                Mesh2d(slot_mesh.clone()),
+                MeshMaterial2d(slot_material.clone()),
+                Transform::from_translation(layout.translation(pos).extend(1.)),
+                Pickable::default(),
+            ))
+            .observe(on_click_slot)
+            .observe(on_drag_over_slot)
+            .id();
+
+        match slot {
+            Slot::Empty => {}
+            Slot::Cell(fuel) => {
+                commands.spawn((
+                    ChildOf(entity),
+                    Mesh2d(cell_mesh.clone()),
+                    MeshMaterial2d(cell_material.clone()),
+                    Transform::from_xyz(0., 0., 1.),
+                    Pickable::IGNORE,
+                ));
+                commands.spawn((
+                    ChildOf(entity),
+                    Mesh2d(fuel_mesh.clone()),
+                    MeshMaterial2d(match fuel {
+                        None => random_fuel_material.clone(),
+                        Some(fuel) => fuel_materials[&fuel].clone(),
+                    }),
+                    Transform::from_xyz(0., 0., 2.),
+                    Pickable::IGNORE,
+                ));
+            }
+            Slot::ControlRod => {
+                commands.spawn((
+                    ChildOf(entity),
+                    Mesh2d(rod_mesh.clone()),
+                    MeshMaterial2d(rod_material.clone()),
+                    Transform::from_xyz(0., 0., 1.),
+                    Pickable::IGNORE,
+                ));
+            }
+        }
+    }
+}
+
+/// Puts the brush's slot at `slot`, leaving the layout unchanged if it's already there.
+fn paint(slot: &EditorSlot, brush: Brush, layout: &mut ResMut<EditorLayout>) {
+    if layout.get(slot.0) != Some(brush.slot()) {
+        layout.set(slot.0, brush.slot());
+    }
+}
+
+fn on_click_slot(
+    trigger: Trigger<Pointer<Click>>,
+    slots: Query<&EditorSlot>,
+    brush: Res<Brush>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    if let Ok(slot) = slots.get(trigger.target()) {
+        paint(slot, *brush, &mut layout);
+    }
+}
+
+/// Paints every slot the pointer is dragged over.
+fn on_drag_over_slot(
+    trigger: Trigger<Pointer<Over>>,
+    input: Res<ButtonInput<MouseButton>>,
+    slots: Query<&EditorSlot>,
+    brush: Res<Brush>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    if !input.pressed(MouseButton::Left) {
+        return;
+    }
+    if let Ok(slot) = slots.get(trigger.target()) {
+        paint(slot, *brush, &mut layout);
+    }
+}
+
+fn on_click_brush(
+    trigger: Trigger<Pointer<Click>>,
+    buttons: Query<&BrushButton>,
+    mut brush: ResMut<Brush>,
+) {
+    if let Ok(button) = buttons.get(trigger.target()) {
+        brush.set_if_neq(button.0);
+    }
+}
+
+fn update_brush_buttons(
+    brush: Res<Brush>,
+    buttons: Query<(&BrushButton, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (button, material) in &buttons {
+        if let Some(material) = materials.get_mut(material) {
+            material.color = brush_button_color(button.0, *brush);
+        }
+    }
+}
+
+fn update_size_label(layout: Res<EditorLayout>, mut label: Single<&mut Text2d, With<SizeLabel>>) {
+    let lattice = match layout.lattice {
+        Lattice::Square => "square",
+        Lattice::Hex => "hex",
+    };
+    label.0 = format!("{} x {} {lattice}", layout.rows, layout.columns);
+}
+
+fn set_status(status: &mut Text2d, text: impl Into<String>) {
+    status.0 = text.into();
+}
+
+fn on_click_classic(_: Trigger<Pointer<Click>>, mut layout: ResMut<EditorLayout>) {
+    layout.0 = ReactorLayout::classic(layout.lattice);
+}
+
+/// Switches between the square and the hexagonal lattice, starting over from its classic layout.
+fn on_click_lattice(_: Trigger<Pointer<Click>>, mut layout: ResMut<EditorLayout>) {
+    layout.0 = ReactorLayout::classic(match layout.lattice {
+        Lattice::Square => Lattice::Hex,
+        Lattice::Hex => Lattice::Square,
+    });
+}
+
+fn on_click_test_run(
+    _: Trigger<Pointer<Click>>,
+    editing: Res<EditorLayout>,
+    mut layout: ResMut<ReactorLayout>,
+    mut status: Single<&mut Text2d, With<StatusLabel>>,
+    resource_handles: Res<ResourceHandles>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if let Err(err) = editing.validate() {
+        set_status(&mut status, err.to_string());
+        return;
+    }
+
+    *layout = editing.0.clone();
+    if resource_handles.is_all_done() {
+        next_screen.set(Screen::Gameplay);
+    } else {
+        next_screen.set(Screen::Loading);
+    }
+}
+
+/// Writes the layout to disk, which makes it the layout of new runs.
+#[cfg(not(target_family = "wasm"))]
+fn on_click_save(
+    _: Trigger<Pointer<Click>>,
+    editing: Res<EditorLayout>,
+    mut status: Single<&mut Text2d, With<StatusLabel>>,
+) {
+    use crate::gameplay::LAYOUT_SAVE_PATH;
+
+    match editing.save(LAYOUT_SAVE_PATH) {
+        Ok(()) => set_status(&mut status, "saved"),
+        Err(err) => {
+            warn!("failed to save layout to {LAYOUT_SAVE_PATH}: {err}");
+            set_status(&mut status, err.to_string());
+        }
+    }
+}
+
+/// Loads the saved layout, or the shipped one if none was saved.
+#[cfg(not(target_family = "wasm"))]
+fn on_click_load(
+    _: Trigger<Pointer<Click>>,
+    mut editing: ResMut<EditorLayout>,
+    layout_assets: Option<Res<LayoutAssets>>,
+    assets: Res<Assets<ReactorLayout>>,
+    mut status: Single<&mut Text2d, With<StatusLabel>>,
+) {
+    if let Some(saved) = ReactorLayout::load_saved() {
+        editing.0 = saved;
+        set_status(&mut status, "loaded");
+    } else if let Some(shipped) = layout_assets.and_then(|handles| assets.get(&handles.layout)) {
+        editing.0 = shipped.clone();
+        set_status(&mut status, "nothing saved, loaded the shipped layout");
+    } else {
+        set_status(&mut status, "nothing saved");
+    }
+}
+
+fn on_click_back(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
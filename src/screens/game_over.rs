@@ -1,20 +1,26 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{gameplay::CrtSettings, menus::Menu};
+use crate::{
+    gameplay::{CrtSettings, Difficulty},
+    menus::Menu,
+};
 
 use super::Screen;
 
-#[derive(Reflect, Clone, Copy, Eq, PartialEq)]
+#[derive(Reflect, Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum GameOverCause {
     PlayerAbandoned,
     NotEnoughPower,
     Explosion,
+    Overheating,
 }
 
 #[derive(Event, Component, Reflect, Clone, Copy)]
 pub struct GameOver {
     pub power_generated: usize,
     pub cause: GameOverCause,
+    pub difficulty: Difficulty,
 }
 
 pub(super) fn plugin(app: &mut App) {
@@ -1,5 +1,6 @@
 //! The game's main screen states and transitions between them.
 
+mod editor;
 pub mod game_over;
 mod gameplay;
 mod loading;
@@ -8,10 +9,13 @@ mod title;
 
 use bevy::prelude::*;
 
+pub use editor::EditorLayout;
+
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
 
     app.add_plugins((
+        editor::plugin,
         gameplay::plugin,
         loading::plugin,
         splash::plugin,
@@ -30,4 +34,5 @@ pub enum Screen {
     Loading,
     Gameplay,
     GameOver,
+    Editor,
 }